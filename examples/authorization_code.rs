@@ -0,0 +1,30 @@
+use netatmo_rs::{ClientCredentials, NetatmoClient, RedirectListener, Scope};
+use std::env;
+
+fn main() {
+    env_logger::init();
+
+    let client_id = env::var_os("NETATMO_CLIENT_ID")
+        .expect("Environment variable 'NETATMO_CLIENT_ID' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let client_secret = env::var_os("NETATMO_CLIENT_SECRET")
+        .expect("Environment variable 'NETATMO_CLIENT_SECRET' is not set.")
+        .to_string_lossy()
+        .to_string();
+
+    let client_credentials = ClientCredentials {
+        client_id: &client_id,
+        client_secret: &client_secret,
+    };
+    let scopes = vec![Scope::ReadStation];
+
+    let listener = RedirectListener::bind("127.0.0.1:8080").expect("Failed to bind redirect listener");
+    let flow = NetatmoClient::new(&client_credentials).authorization_code_flow(listener.redirect_uri(), &scopes);
+    println!("Please open {} in your browser.", flow.authorize_url());
+
+    let client = flow.authenticate(&listener).expect("Failed to authenticate");
+    let token = client.token();
+
+    println!("{:#?}", token);
+}
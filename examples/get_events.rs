@@ -0,0 +1,54 @@
+use netatmo_rs::{get_next_events::EventHistory, ClientCredentials, NetatmoClient, Scope};
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn main() {
+    let client_id = env::var_os("NETATMO_CLIENT_ID")
+        .expect("Environment variable 'NETATMO_CLIENT_ID' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let client_secret = env::var_os("NETATMO_CLIENT_SECRET")
+        .expect("Environment variable 'NETATMO_CLIENT_SECRET' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let username = env::var_os("NETATMO_USERNAME")
+        .expect("Environment variable 'NETATMO_USERNAME' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let password = env::var_os("NETATMO_PASSWORD")
+        .expect("Environment variable 'NETATMO_PASSWORD' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let home_id = env::var_os("NETATMO_HOME_ID")
+        .expect("Environment variable 'NETATMO_HOME_ID' is not set")
+        .to_string_lossy()
+        .to_string();
+    let event_id = env::var_os("NETATMO_EVENT_ID")
+        .expect("Environment variable 'NETATMO_EVENT_ID' is not set")
+        .to_string_lossy()
+        .to_string();
+
+    let client_credentials = ClientCredentials {
+        client_id: &client_id,
+        client_secret: &client_secret,
+    };
+    let scopes = vec![Scope::ReadCamera, Scope::ReadPresence];
+
+    let client = NetatmoClient::new(&client_credentials)
+        .authenticate(&username, &password, &scopes)
+        .expect("Failed to authenticate");
+    // `event_id` is, e.g., the oldest event returned by `get_home_data`; stop at events older than a day
+    let until = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to get current time")
+        .as_secs() as i64
+        - 86400;
+    for event in EventHistory::new(&client, &home_id, &event_id)
+        .until(until)
+        .page_size(30)
+    {
+        println!("{:#?}", event.expect("Failed to get events"));
+    }
+}
@@ -0,0 +1,46 @@
+use netatmo_rs::{add_webhook, ClientCredentials, Netatmo, NetatmoClient, Scope, WebhookServer};
+use std::env;
+
+fn main() {
+    let client_id = env::var_os("NETATMO_CLIENT_ID")
+        .expect("Environment variable 'NETATMO_CLIENT_ID' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let client_secret = env::var_os("NETATMO_CLIENT_SECRET")
+        .expect("Environment variable 'NETATMO_CLIENT_SECRET' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let username = env::var_os("NETATMO_USERNAME")
+        .expect("Environment variable 'NETATMO_USERNAME' is not set.")
+        .to_string_lossy()
+        .to_string();
+    let password = env::var_os("NETATMO_PASSWORD")
+        .expect("Environment variable 'NETATMO_PASSWORD' is not set.")
+        .to_string_lossy()
+        .to_string();
+    // Public URL, e.g., of a reverse proxy, that forwards to the local port of the webhook server
+    let webhook_url = env::var_os("NETATMO_WEBHOOK_URL")
+        .expect("Environment variable 'NETATMO_WEBHOOK_URL' is not set")
+        .to_string_lossy()
+        .to_string();
+
+    let client_credentials = ClientCredentials {
+        client_id: &client_id,
+        client_secret: &client_secret,
+    };
+    let scopes = vec![Scope::ReadCamera, Scope::ReadPresence];
+
+    // Listens on all interfaces, so only accept calls signed by Netatmo with the client secret
+    let server = WebhookServer::bind("0.0.0.0:8080")
+        .expect("Failed to bind webhook server")
+        .verify_signatures(&client_credentials);
+    NetatmoClient::new(&client_credentials)
+        .authenticate(&username, &password, &scopes)
+        .expect("Failed to authenticate")
+        .add_webhook(&add_webhook::Parameters::new(&webhook_url))
+        .expect("Failed to add webhook");
+
+    server
+        .serve(|payload| println!("{:#?}", payload))
+        .expect("Failed to receive webhook calls");
+}
@@ -1,37 +1,110 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use failure::Fail;
-use log::trace;
-use reqwest::blocking::{Client, Response};
+use log::{debug, trace, warn};
 use reqwest::StatusCode;
-use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use serde_json::Value;
 
-use authenticate::{Scope, Token};
-use get_home_status::HomeStatus;
-use get_homes_data::HomesData;
-use get_measure::Measure;
-use get_station_data::StationData;
+use authenticate::Token;
+use builder::ClientBuilder;
+use picture_cache::PictureCache;
+use rate_limit::RateLimiter;
+use response::ApiResponse;
+use retry::RetryPolicy;
+use token_store::TokenStore;
 
-use crate::errors::{Error, ErrorKind, Result};
+use crate::errors::{ApiErrorCode, Error, ErrorKind, Result};
 
+#[cfg(feature = "async")]
+pub use asynchronous::{AsyncAuthenticatedClient, AsyncNetatmo, AsyncUnauthenticatedClient};
+#[cfg(feature = "blocking")]
+pub use blocking::{AuthenticatedClient, Netatmo, UnauthenticatedClient};
+
+pub mod add_webhook;
+#[cfg(feature = "async")]
+mod asynchronous;
 pub mod authenticate;
+pub mod authorization_code;
+#[cfg(feature = "blocking")]
+mod blocking;
+pub mod builder;
+pub mod create_new_home_schedule;
+pub mod delete_home_schedule;
+pub mod drop_webhook;
+pub mod get_camera_picture;
+pub mod get_events;
+pub mod get_events_until;
+pub mod get_home_data;
 pub mod get_home_status;
 pub mod get_homes_data;
+pub mod get_last_event_of;
 pub mod get_measure;
+pub mod get_next_events;
+pub mod get_public_data;
+pub mod get_room_measure;
 pub mod get_station_data;
+mod local_http;
+pub mod picture_cache;
+pub mod rate_limit;
+pub mod rename_home_schedule;
+pub mod response;
+pub mod retry;
+#[cfg(feature = "schedule_evaluator")]
+pub mod schedule_evaluator;
+pub mod set_persons_away;
+pub mod set_persons_home;
 pub mod set_room_thermpoint;
+pub mod set_state;
+pub mod set_therm_mode;
+pub mod switch_home_schedule;
+pub mod sync_home_schedule;
+#[cfg(test)]
+mod test_support;
+pub mod token_store;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+pub mod weekly_schedule;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.netatmo.com";
+
+/// Default for `ClientBuilder::error_body_limit`
+pub const DEFAULT_ERROR_BODY_LIMIT: usize = 4096;
+
+/// Tokens expiring within this margin are refreshed before the next API call
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// Settings shared by the unauthenticated and the authenticated client
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    base_url: String,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    error_body_limit: Option<usize>,
+    lenient: bool,
+    strict: bool,
+    picture_cache: Option<PictureCache>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            retry_policy: RetryPolicy::none(),
+            rate_limiter: None,
+            error_body_limit: Some(DEFAULT_ERROR_BODY_LIMIT),
+            lenient: false,
+            strict: false,
+            picture_cache: None,
+        }
+    }
+}
 
-pub trait Netatmo {
-    fn get_home_status(&self, parameters: &get_home_status::Parameters) -> Result<HomeStatus>;
-    fn get_homes_data(&self, parameters: &get_homes_data::Parameters) -> Result<HomesData>;
-    fn get_station_data(&self, device_id: &str) -> Result<StationData>;
-    fn get_homecoachs_data(&self, device_id: &str) -> Result<StationData>;
-    fn get_measure(&self, parameters: &get_measure::Parameters) -> Result<Measure>;
-    fn set_room_thermpoint(
-        &self,
-        parameters: &set_room_thermpoint::Parameters,
-    ) -> Result<set_room_thermpoint::Response>;
+impl Config {
+    /// Resolves an endpoint path like `api/getmeasure` against the base URL
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
 }
 
 #[derive(Debug)]
@@ -40,86 +113,207 @@ pub struct ClientCredentials<'a> {
     pub client_secret: &'a str,
 }
 
+#[derive(Debug)]
+struct OwnedClientCredentials {
+    client_id: String,
+    client_secret: String,
+}
+
+impl<'a> From<&'a ClientCredentials<'a>> for OwnedClientCredentials {
+    fn from(cc: &'a ClientCredentials) -> Self {
+        OwnedClientCredentials {
+            client_id: cc.client_id.to_string(),
+            client_secret: cc.client_secret.to_string(),
+        }
+    }
+}
+
+impl<'a> From<&'a OwnedClientCredentials> for ClientCredentials<'a> {
+    fn from(cc: &'a OwnedClientCredentials) -> Self {
+        ClientCredentials {
+            client_id: &cc.client_id,
+            client_secret: &cc.client_secret,
+        }
+    }
+}
+
 pub struct NetatmoClient {}
 
 impl<'a> NetatmoClient {
+    #[cfg(feature = "blocking")]
     #[allow(clippy::new_ret_no_self)]
     pub fn new(client_credentials: &'a ClientCredentials) -> UnauthenticatedClient<'a> {
-        UnauthenticatedClient {
-            client_credentials,
-            http: Client::new(),
-        }
+        UnauthenticatedClient::new(client_credentials, reqwest::blocking::Client::new(), Config::default())
     }
 
+    /// Configures base URL, timeouts, proxy etc. before creating a client
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Creates a client from a previously obtained token
+    ///
+    /// The token cannot be refreshed unless client credentials are added via
+    /// `AuthenticatedClient::with_client_credentials`.
+    #[cfg(feature = "blocking")]
     pub fn with_token(token: Token) -> AuthenticatedClient {
-        AuthenticatedClient {
-            token,
-            http: Client::new(),
-        }
+        AuthenticatedClient::new(token, reqwest::blocking::Client::new(), Config::default())
+    }
+
+    #[cfg(feature = "async")]
+    pub fn new_async(client_credentials: &'a ClientCredentials) -> AsyncUnauthenticatedClient<'a> {
+        AsyncUnauthenticatedClient::new(client_credentials, reqwest::Client::new(), Config::default())
+    }
+
+    /// Creates an async client from a previously obtained token; cf. `NetatmoClient::with_token`
+    #[cfg(feature = "async")]
+    pub fn with_token_async(token: Token) -> AsyncAuthenticatedClient {
+        AsyncAuthenticatedClient::new(token, reqwest::Client::new(), Config::default())
     }
 }
 
-#[derive(Debug)]
-pub struct UnauthenticatedClient<'a> {
-    client_credentials: &'a ClientCredentials<'a>,
-    http: Client,
+/// Netatmo signals an expired access token with `ApiErrorCode::AccessTokenExpired`
+fn is_expired_token_error(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::ApiCallFailed {
+            code: ApiErrorCode::AccessTokenExpired,
+            ..
+        }
+    )
 }
 
-impl<'a> UnauthenticatedClient<'a> {
-    pub fn authenticate(self, username: &'a str, password: &'a str, scopes: &[Scope]) -> Result<AuthenticatedClient> {
-        authenticate::get_token(&self, username, password, scopes)
-            .map(|token| AuthenticatedClient { token, http: self.http })
-            .map_err(|e| e.context(ErrorKind::AuthenticationFailed).into())
-    }
+/// Netatmo signals an exceeded per-user quota with `ApiErrorCode::MaximumUsageReached`
+fn is_rate_limit_error(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::ApiCallFailed {
+            code: ApiErrorCode::MaximumUsageReached,
+            ..
+        }
+    )
+}
 
-    pub(crate) fn call<T>(&self, name: &'static str, url: &str, params: &HashMap<&str, &str>) -> Result<T>
-    where
-        T: DeserializeOwned,
-    {
-        api_call(name, &self.http, url, params)
+/// Replaces `token` by the token of `token_store` if that has been issued later and saves `token` to the store otherwise
+///
+/// A client starting with an old token must not overwrite the newer one another client sharing the store has saved.
+fn sync_with_token_store(token_store: &dyn TokenStore, token: &mut Token) -> Result<()> {
+    match token_store.load() {
+        Ok(Some(stored)) if stored.issued_at > token.issued_at => {
+            debug!("Using newer token from token store");
+            *token = stored;
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to load token from token store; replacing it: {}", e),
     }
+
+    token_store.save(token)
 }
 
-pub struct AuthenticatedClient {
-    token: Token,
-    http: Client,
+/// Returns the token of `token_store` if another client sharing the store already replaced `token`
+fn newer_stored_token(token_store: Option<&dyn TokenStore>, token: &Token) -> Option<Token> {
+    let stored = match token_store.map(|x| x.load()) {
+        Some(Ok(Some(stored))) => stored,
+        Some(Err(e)) => {
+            warn!("Failed to load token from token store: {}", e);
+            return None;
+        }
+        _ => return None,
+    };
+    if stored.access_token != token.access_token && !stored.expires_within(TOKEN_REFRESH_MARGIN) {
+        Some(stored)
+    } else {
+        None
+    }
 }
 
-impl AuthenticatedClient {
-    pub fn token(&self) -> &Token {
-        &self.token
+fn save_refreshed_token(token_store: Option<&dyn TokenStore>, token: &Token) {
+    if let Some(token_store) = token_store {
+        // The refreshed token is valid regardless, so a failing store must not fail the API call.
+        if let Err(e) = token_store.save(token) {
+            warn!("Failed to save refreshed token: {}", e);
+        }
     }
+}
 
-    pub(crate) fn call<'a, T>(&'a self, name: &'static str, url: &str, params: &mut HashMap<&str, &'a str>) -> Result<T>
-    where
-        T: DeserializeOwned,
-    {
-        params.insert("access_token", &self.token.access_token);
-        api_call(name, &self.http, url, params)
+/// Result type of an API call built from the raw HTTP response
+///
+/// JSON responses, i.e., all `ApiResponse` types, go through `handle_response`; binary responses like camera
+/// pictures implement this directly.
+pub(crate) trait FromHttpResponse: Sized {
+    fn from_http_response(
+        config: &Config,
+        name: &'static str,
+        status: StatusCode,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Self>;
+}
+
+impl<T: ApiResponse> FromHttpResponse for T {
+    fn from_http_response(
+        config: &Config,
+        name: &'static str,
+        status: StatusCode,
+        _content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Self> {
+        handle_response(config, name, status, &String::from_utf8_lossy(body))
     }
 }
 
-fn api_call<T>(name: &'static str, http: &Client, url: &str, params: &HashMap<&str, &str>) -> Result<T>
+/// Body of an authenticated API call; most endpoints take a form, those with nested parameters JSON
+#[derive(Clone, Copy)]
+pub(crate) enum RequestBody<'a> {
+    Form(&'a HashMap<&'a str, &'a str>),
+    Json(&'a Value),
+}
+
+/// Maps a response of API call `name` to `T` or to the error reported by Netatmo
+fn handle_response<T>(config: &Config, name: &'static str, status: StatusCode, body: &str) -> Result<T>
 where
-    T: DeserializeOwned,
+    T: ApiResponse,
 {
-    let res = http
-        .post(url)
-        .form(&params)
-        .send()
-        .map_err(|e| e.context(ErrorKind::FailedToSendRequest))?
-        .general_err_handler(name, StatusCode::OK)?;
-
-    let status = res.status();
-    let body = res.text().map_err(|e| e.context(ErrorKind::FailedToReadResponse))?;
+    if status != StatusCode::OK {
+        return Err(api_call_failure(name, status, body));
+    }
+
     trace!("Sucessful ({:?}) repsone: '{}'", status, body);
-    serde_json::from_str::<T>(&body).map_err(|e| e.context(ErrorKind::JsonDeserializationFailed).into())
-}
+    let res = if config.lenient {
+        response::deserialize_lenient(body)
+    } else {
+        response::deserialize(body)
+    };
+    let res: T = res.map_err(|e| {
+        let kind = ErrorKind::JsonDeserializationFailed {
+            name,
+            path: e.path().to_string(),
+            msg: e.inner().to_string(),
+            body: truncate(body, config.error_body_limit),
+        };
+        Error::from(e.into_inner().context(kind))
+    })?;
 
-pub(crate) trait GeneralErrHandler {
-    type T: std::marker::Sized;
+    if config.strict {
+        let fields = res.unmapped_fields();
+        if !fields.is_empty() {
+            return Err(ErrorKind::UnmappedFields { name, fields }.into());
+        }
+    }
 
-    fn general_err_handler(self, name: &'static str, expected_status: StatusCode) -> Result<Self::T>;
+    Ok(res)
+}
+
+/// Cuts `s` to at most `limit` bytes without splitting a character
+fn truncate(s: &str, limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) if s.len() > limit => {
+            let end = (0..=limit).rev().find(|x| s.is_char_boundary(*x)).unwrap_or(0);
+            format!("{}...", &s[..end])
+        }
+        _ => s.to_string(),
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -134,69 +328,79 @@ struct ApiErrorDetails {
     message: String,
 }
 
-impl GeneralErrHandler for Response {
-    type T = Response;
-
-    fn general_err_handler(self, name: &'static str, expected_status: StatusCode) -> Result<Self> {
-        match self.status() {
-            code if code == expected_status => Ok(self),
-            code @ StatusCode::BAD_REQUEST
-            | code @ StatusCode::UNAUTHORIZED
-            | code @ StatusCode::FORBIDDEN
-            | code @ StatusCode::NOT_FOUND
-            | code @ StatusCode::NOT_ACCEPTABLE
-            | code @ StatusCode::INTERNAL_SERVER_ERROR => {
-                let body = self.text().map_err(|e| {
-                    e.context(ErrorKind::UnknownApiCallFailure {
-                        name,
-                        status_code: code.as_u16(),
-                    })
-                })?;
-                let err: ApiError = serde_json::from_str(&body).map_err(|e| {
-                    e.context(ErrorKind::UnknownApiCallFailure {
-                        name,
-                        status_code: code.as_u16(),
-                    })
-                })?;
-                Err(Error::from(ErrorKind::ApiCallFailed {
-                    name,
-                    code: err.details.code,
-                    msg: err.details.message,
-                }))
-            }
-            code => Err(Error::from(ErrorKind::UnknownApiCallFailure {
+pub(crate) fn api_call_failure(name: &'static str, status: StatusCode, body: &str) -> Error {
+    match status {
+        code @ StatusCode::BAD_REQUEST
+        | code @ StatusCode::UNAUTHORIZED
+        | code @ StatusCode::FORBIDDEN
+        | code @ StatusCode::NOT_FOUND
+        | code @ StatusCode::NOT_ACCEPTABLE
+        | code @ StatusCode::INTERNAL_SERVER_ERROR => match serde_json::from_str::<ApiError>(body) {
+            Ok(err) => Error::from(ErrorKind::ApiCallFailed {
                 name,
+                code: err.details.code.into(),
+                msg: err.details.message,
                 status_code: code.as_u16(),
-            })),
-        }
+            }),
+            Err(e) => e
+                .context(ErrorKind::UnknownApiCallFailure {
+                    name,
+                    status_code: code.as_u16(),
+                })
+                .into(),
+        },
+        code => Error::from(ErrorKind::UnknownApiCallFailure {
+            name,
+            status_code: code.as_u16(),
+        }),
     }
 }
 
-impl Netatmo for AuthenticatedClient {
-    fn get_homes_data(&self, parameters: &get_homes_data::Parameters) -> Result<HomesData> {
-        get_homes_data::get_homes_data(&self, parameters)
-    }
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
 
-    fn get_home_status(&self, parameters: &get_home_status::Parameters) -> Result<HomeStatus> {
-        get_home_status::get_home_status(&self, parameters)
-    }
+    use super::*;
 
-    fn get_station_data(&self, device_id: &str) -> Result<StationData> {
-        get_station_data::get_station_data(&self, device_id)
-    }
+    mod handle_response {
+        use super::*;
 
-    fn get_homecoachs_data(&self, device_id: &str) -> Result<StationData> {
-        get_station_data::get_homecoachs_data(self, device_id)
-    }
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            #[allow(dead_code)]
+            modules: Vec<Module>,
+        }
 
-    fn get_measure(&self, parameters: &get_measure::Parameters) -> Result<Measure> {
-        get_measure::get_measure(&self, parameters)
-    }
+        impl ApiResponse for Response {}
+
+        #[derive(Debug, Deserialize)]
+        struct Module {
+            #[allow(dead_code)]
+            battery_vp: u64,
+        }
+
+        #[test]
+        fn report_path_of_deserialization_failure() {
+            let json = r#"{"modules": [{"battery_vp": 5000}, {"battery_vp": "low"}]}"#;
+
+            let res: Result<Response> = handle_response(&Config::default(), "get_station_data", StatusCode::OK, json);
 
-    fn set_room_thermpoint(
-        &self,
-        parameters: &set_room_thermpoint::Parameters,
-    ) -> Result<set_room_thermpoint::Response> {
-        set_room_thermpoint::set_room_thermpoint(&self, parameters)
+            match res.map_err(|e| e.kind().clone()) {
+                Err(ErrorKind::JsonDeserializationFailed { name, path, body, .. }) => {
+                    assert_that(&name).is_equal_to("get_station_data");
+                    assert_that(&path).is_equal_to("modules[1].battery_vp".to_string());
+                    assert_that(&body).is_equal_to(json.to_string());
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+
+        #[test]
+        fn truncate_body() {
+            assert_that(&truncate("{\"name\": \"Küche\"}", Some(13)))
+                .is_equal_to("{\"name\": \"K\u{fc}...".to_string());
+            assert_that(&truncate("{\"name\": \"Küche\"}", Some(12))).is_equal_to("{\"name\": \"K...".to_string());
+            assert_that(&truncate("{}", None)).is_equal_to("{}".to_string());
+        }
     }
 }
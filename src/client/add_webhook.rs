@@ -0,0 +1,112 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{client::response::ApiResponse, errors::Result};
+
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+pub struct Parameters<'a> {
+    url: &'a str,
+    app_type: Option<AppType>,
+}
+
+impl<'a> Parameters<'a> {
+    /// Registers `url` to receive the events of the authenticated user
+    pub fn new(url: &'a str) -> Self {
+        Parameters { url, app_type: None }
+    }
+
+    /// Restricts the events to those of one product line; Netatmo defaults to `AppType::Security`
+    pub fn app_type(self, app_type: AppType) -> Self {
+        Parameters {
+            app_type: Some(app_type),
+            ..self
+        }
+    }
+}
+
+pub enum AppType {
+    Security,
+    Energy,
+}
+
+impl fmt::Display for AppType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            AppType::Security => "app_security",
+            AppType::Energy => "app_energy",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        map.insert("url", p.url.to_string());
+        if let Some(ref app_type) = p.app_type {
+            map.insert("app_types", app_type.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: Option<usize>,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#addwebhook
+#[cfg(feature = "blocking")]
+pub(crate) fn add_webhook(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("add_webhook", "api/addwebhook", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn add_webhook_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("add_webhook", "api/addwebhook", &params).await
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::{
+        local_http::FakeServer,
+        test_support::{client, form, now, token, RESPONSE},
+        Netatmo,
+    };
+
+    #[test]
+    fn send_url_and_app_type() {
+        let server = FakeServer::start(vec![(200, RESPONSE)]);
+        let client = client(&server, token(now()));
+        let parameters = Parameters::new("https://example.com/netatmo").app_type(AppType::Energy);
+
+        let res = client.add_webhook(&parameters);
+
+        assert_that(&res).is_ok();
+        let requests = server.requests();
+        let form = form(&requests[0]);
+        assert_that(&requests[0].target).is_equal_to("/api/addwebhook".to_string());
+        assert_that(&form["url"]).is_equal_to("https://example.com/netatmo".to_string());
+        assert_that(&form["app_types"]).is_equal_to("app_energy".to_string());
+    }
+}
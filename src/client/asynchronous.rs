@@ -0,0 +1,734 @@
+use std::{collections::HashMap, future::Future, path::Path, sync::Arc};
+
+use failure::Fail;
+use log::{debug, warn};
+use reqwest::{header::CONTENT_TYPE, Client, Response};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::{
+    client::{
+        add_webhook,
+        authenticate::{self, Scope, Token},
+        authorization_code::AuthorizationCodeFlow,
+        create_new_home_schedule, delete_home_schedule, drop_webhook,
+        get_camera_picture::{self, Picture},
+        get_events::{self, Events, EventsList},
+        get_events_until,
+        get_home_data::{self, HomeData},
+        get_home_status::{self, HomeStatus},
+        get_homes_data::{self, HomesData},
+        get_last_event_of,
+        get_measure::{self, Measure},
+        get_next_events,
+        get_public_data::{self, PublicData},
+        get_room_measure::{self, RoomMeasure},
+        get_station_data::{self, StationData},
+        is_expired_token_error, is_rate_limit_error, newer_stored_token,
+        picture_cache::PictureCache,
+        rate_limit::RateLimiter,
+        rename_home_schedule, save_refreshed_token, set_persons_away, set_persons_home, set_room_thermpoint, set_state,
+        set_therm_mode, switch_home_schedule, sync_home_schedule, sync_with_token_store,
+        token_store::TokenStore,
+        ClientCredentials, Config, FromHttpResponse, OwnedClientCredentials, RequestBody, TOKEN_REFRESH_MARGIN,
+    },
+    errors::{Error, ErrorKind, Result},
+};
+
+/// Async counterpart of `Netatmo`
+pub trait AsyncNetatmo {
+    fn add_webhook(
+        &self,
+        parameters: &add_webhook::Parameters,
+    ) -> impl Future<Output = Result<add_webhook::Response>> + Send;
+    fn drop_webhook(
+        &self,
+        parameters: &drop_webhook::Parameters,
+    ) -> impl Future<Output = Result<drop_webhook::Response>> + Send;
+    fn get_camera_picture(
+        &self,
+        parameters: &get_camera_picture::Parameters,
+    ) -> impl Future<Output = Result<Picture>> + Send;
+    /// Downloads a camera picture to `path` and returns its content type
+    fn download_camera_picture(
+        &self,
+        parameters: &get_camera_picture::Parameters,
+        path: &Path,
+    ) -> impl Future<Output = Result<String>> + Send;
+    fn get_events(&self, parameters: &get_events::Parameters) -> impl Future<Output = Result<Events>> + Send;
+    fn get_events_until(
+        &self,
+        parameters: &get_events_until::Parameters,
+    ) -> impl Future<Output = Result<EventsList>> + Send;
+    fn get_home_data(&self, parameters: &get_home_data::Parameters) -> impl Future<Output = Result<HomeData>> + Send;
+    fn get_home_status(
+        &self,
+        parameters: &get_home_status::Parameters,
+    ) -> impl Future<Output = Result<HomeStatus>> + Send;
+    fn get_homes_data(&self, parameters: &get_homes_data::Parameters)
+        -> impl Future<Output = Result<HomesData>> + Send;
+    fn get_station_data(&self, device_id: &str) -> impl Future<Output = Result<StationData>> + Send;
+    fn get_homecoachs_data(&self, device_id: &str) -> impl Future<Output = Result<StationData>> + Send;
+    fn get_last_event_of(
+        &self,
+        parameters: &get_last_event_of::Parameters,
+    ) -> impl Future<Output = Result<EventsList>> + Send;
+    fn get_measure(&self, parameters: &get_measure::Parameters) -> impl Future<Output = Result<Measure>> + Send;
+    fn get_room_measure(
+        &self,
+        parameters: &get_room_measure::Parameters,
+    ) -> impl Future<Output = Result<RoomMeasure>> + Send;
+    fn get_next_events(
+        &self,
+        parameters: &get_next_events::Parameters,
+    ) -> impl Future<Output = Result<EventsList>> + Send;
+    fn get_public_data(
+        &self,
+        parameters: &get_public_data::Parameters,
+    ) -> impl Future<Output = Result<PublicData>> + Send;
+    fn set_persons_away(
+        &self,
+        parameters: &set_persons_away::Parameters,
+    ) -> impl Future<Output = Result<set_persons_away::Response>> + Send;
+    fn set_persons_home(
+        &self,
+        parameters: &set_persons_home::Parameters,
+    ) -> impl Future<Output = Result<set_persons_home::Response>> + Send;
+    fn set_room_thermpoint(
+        &self,
+        parameters: &set_room_thermpoint::Parameters,
+    ) -> impl Future<Output = Result<set_room_thermpoint::Response>> + Send;
+    fn set_therm_mode(
+        &self,
+        parameters: &set_therm_mode::Parameters,
+    ) -> impl Future<Output = Result<set_therm_mode::Response>> + Send;
+    fn create_new_home_schedule(
+        &self,
+        parameters: &create_new_home_schedule::Parameters,
+    ) -> impl Future<Output = Result<create_new_home_schedule::Response>> + Send;
+    fn delete_home_schedule(
+        &self,
+        parameters: &delete_home_schedule::Parameters,
+    ) -> impl Future<Output = Result<delete_home_schedule::Response>> + Send;
+    fn rename_home_schedule(
+        &self,
+        parameters: &rename_home_schedule::Parameters,
+    ) -> impl Future<Output = Result<rename_home_schedule::Response>> + Send;
+    fn switch_home_schedule(
+        &self,
+        parameters: &switch_home_schedule::Parameters,
+    ) -> impl Future<Output = Result<switch_home_schedule::Response>> + Send;
+    fn sync_home_schedule(
+        &self,
+        parameters: &sync_home_schedule::Parameters,
+    ) -> impl Future<Output = Result<sync_home_schedule::Response>> + Send;
+    fn set_state(&self, parameters: &set_state::Parameters)
+        -> impl Future<Output = Result<set_state::Response>> + Send;
+}
+
+#[derive(Debug)]
+pub struct AsyncUnauthenticatedClient<'a> {
+    pub(crate) client_credentials: &'a ClientCredentials<'a>,
+    http: Client,
+    config: Config,
+}
+
+impl<'a> AsyncUnauthenticatedClient<'a> {
+    pub(crate) fn new(client_credentials: &'a ClientCredentials, http: Client, config: Config) -> Self {
+        AsyncUnauthenticatedClient {
+            client_credentials,
+            http,
+            config,
+        }
+    }
+
+    pub async fn authenticate(
+        self,
+        username: &'a str,
+        password: &'a str,
+        scopes: &[Scope],
+    ) -> Result<AsyncAuthenticatedClient> {
+        match authenticate::get_token_async(&self, username, password, scopes).await {
+            Ok(token) => Ok(self.into_authenticated(token)),
+            Err(e) => Err(e.context(ErrorKind::AuthenticationFailed).into()),
+        }
+    }
+
+    /// Starts an OAuth2 authorization code grant redirecting to `redirect_uri`
+    ///
+    /// Use `authorization_code::RedirectListener` to receive the redirect on a local port.
+    pub fn authorization_code_flow(self, redirect_uri: &str, scopes: &[Scope]) -> AuthorizationCodeFlow<Self> {
+        let client_id = self.client_credentials.client_id.to_string();
+        let config = self.config.clone();
+        AuthorizationCodeFlow::new(self, &client_id, &config, redirect_uri, scopes)
+    }
+
+    pub(crate) fn into_authenticated(self, token: Token) -> AsyncAuthenticatedClient {
+        AsyncAuthenticatedClient::new(token, self.http, self.config).with_client_credentials(self.client_credentials)
+    }
+
+    pub(crate) async fn call<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        api_call(name, &self.http, &self.config, path, params).await
+    }
+}
+
+/// Async counterpart of `AuthenticatedClient`
+pub struct AsyncAuthenticatedClient {
+    token: Mutex<Token>,
+    client_credentials: Option<OwnedClientCredentials>,
+    token_store: Option<Arc<dyn TokenStore>>,
+    http: Client,
+    config: Config,
+}
+
+impl AsyncAuthenticatedClient {
+    pub(crate) fn new(token: Token, http: Client, config: Config) -> Self {
+        AsyncAuthenticatedClient {
+            token: Mutex::new(token),
+            client_credentials: None,
+            token_store: None,
+            http,
+            config,
+        }
+    }
+
+    /// Enables automatic token refresh using these client credentials
+    pub fn with_client_credentials(self, client_credentials: &ClientCredentials) -> Self {
+        AsyncAuthenticatedClient {
+            client_credentials: Some(client_credentials.into()),
+            ..self
+        }
+    }
+
+    /// Saves the current token to `token_store` and keeps it up to date whenever the token gets refreshed
+    ///
+    /// If the store already holds a token issued later than the current one, the client switches to that token
+    /// instead. This call accesses the store synchronously; refreshes access it on tokio's blocking thread pool.
+    pub fn with_token_store<S: TokenStore + 'static>(mut self, token_store: S) -> Result<Self> {
+        sync_with_token_store(&token_store, self.token.get_mut())?;
+        Ok(AsyncAuthenticatedClient {
+            token_store: Some(Arc::new(token_store)),
+            ..self
+        })
+    }
+
+    /// Returns a copy of the current token which changes whenever the token gets refreshed
+    pub async fn token(&self) -> Token {
+        self.token.lock().await.clone()
+    }
+
+    /// Returns the rate limiter of this client to check the remaining budget
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.config.rate_limiter.as_deref()
+    }
+
+    /// Returns the picture cache of this client, e.g., to purge it
+    pub fn picture_cache(&self) -> Option<&PictureCache> {
+        self.config.picture_cache.as_ref()
+    }
+
+    /// Refreshes the access token using the refresh token
+    pub async fn refresh_token(&self) -> Result<()> {
+        let mut token = self.token.lock().await;
+        self.refresh_locked_token(&mut token).await
+    }
+
+    pub(crate) async fn call<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Form(params), false).await
+    }
+
+    /// Calls an endpoint that changes state and therefore is only retried if the retry policy allows writes
+    pub(crate) async fn call_write<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Form(params), true).await
+    }
+
+    /// Like `call_write`, but sends `json` as body
+    pub(crate) async fn call_write_json<T>(&self, name: &'static str, path: &str, json: &Value) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Json(json), true).await
+    }
+
+    async fn call_with_retry<T>(&self, name: &'static str, path: &str, body: RequestBody<'_>, write: bool) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        let retry_policy = &self.config.retry_policy;
+        let mut attempt = 1;
+        loop {
+            match self.call_once(name, path, body).await {
+                Err(ref e) if retry_policy.should_retry(e.kind(), attempt, write) => {
+                    let delay = retry_policy.delay(attempt);
+                    warn!(
+                        "Attempt {} of '{}' failed: {}; retrying in {:?}",
+                        attempt, name, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    async fn call_once<T>(&self, name: &'static str, path: &str, body: RequestBody<'_>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        let access_token = self.valid_access_token().await?;
+        match self.call_with_access_token(name, path, body, &access_token).await {
+            Err(ref e) if self.client_credentials.is_some() && is_expired_token_error(e) => {
+                debug!("Access token expired during '{}'; refreshing and retrying", name);
+                let access_token = self.refreshed_access_token(&access_token).await?;
+                self.call_with_access_token(name, path, body, &access_token).await
+            }
+            res => res,
+        }
+    }
+
+    async fn call_with_access_token<T>(
+        &self,
+        name: &'static str,
+        path: &str,
+        body: RequestBody<'_>,
+        access_token: &str,
+    ) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.wait_for_rate_limit().await?;
+        let res = match body {
+            RequestBody::Form(params) => {
+                let mut params = params.clone();
+                params.insert("access_token", access_token);
+                api_call(name, &self.http, &self.config, path, &params).await
+            }
+            RequestBody::Json(json) => api_call_json(name, &self.http, &self.config, path, json, access_token).await,
+        };
+        if let (Err(ref e), Some(rate_limiter)) = (&res, &self.config.rate_limiter) {
+            if is_rate_limit_error(e) {
+                rate_limiter.exhaust();
+            }
+        }
+
+        res
+    }
+
+    async fn wait_for_rate_limit(&self) -> Result<()> {
+        if let Some(ref rate_limiter) = self.config.rate_limiter {
+            while let Some(wait_time) = rate_limiter.acquire_or_wait_time()? {
+                debug!("Rate limit exhausted; waiting {:?}", wait_time);
+                tokio::time::sleep(wait_time).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current access token after refreshing it if it is about to expire
+    async fn valid_access_token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if self.client_credentials.is_some() && token.expires_within(TOKEN_REFRESH_MARGIN) {
+            debug!("Access token expires at {}; refreshing", token.expires_at());
+            self.refresh_locked_token(&mut token).await?;
+        }
+        Ok(token.access_token.clone())
+    }
+
+    /// Returns a refreshed access token unless another caller already replaced `expired_access_token`
+    async fn refreshed_access_token(&self, expired_access_token: &str) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if token.access_token == expired_access_token {
+            self.refresh_locked_token(&mut token).await?;
+        }
+        Ok(token.access_token.clone())
+    }
+
+    async fn refresh_locked_token(&self, token: &mut Token) -> Result<()> {
+        if let Some(ref token_store) = self.token_store {
+            let (token_store, current) = (Arc::clone(token_store), token.clone());
+            let stored = run_blocking(ErrorKind::TokenStoreFailed, move || {
+                newer_stored_token(Some(token_store.as_ref()), &current)
+            })
+            .await;
+            if let Ok(Some(stored)) = stored {
+                debug!("Using newer token from token store");
+                *token = stored;
+                return Ok(());
+            }
+        }
+
+        let client_credentials = self
+            .client_credentials
+            .as_ref()
+            .ok_or_else(|| Error::from(ErrorKind::TokenRefreshFailed))?;
+        *token = authenticate::refresh_token_async(
+            &self.http,
+            &self.config,
+            &client_credentials.into(),
+            &token.refresh_token,
+        )
+        .await
+        .map_err(|e| e.context(ErrorKind::TokenRefreshFailed))?;
+        if let Some(ref token_store) = self.token_store {
+            let (token_store, refreshed) = (Arc::clone(token_store), token.clone());
+            let saved = run_blocking(ErrorKind::TokenStoreFailed, move || {
+                save_refreshed_token(Some(token_store.as_ref()), &refreshed)
+            })
+            .await;
+            if let Err(e) = saved {
+                warn!("Failed to save refreshed token: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs blocking file IO, e.g., of token stores and picture caches, on tokio's thread pool for blocking tasks
+///
+/// Fails with `kind` if the task panics or the runtime shuts down.
+pub(crate) async fn run_blocking<F, T>(kind: ErrorKind, f: F) -> Result<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(|e| e.context(kind).into())
+}
+
+pub(crate) async fn api_call<T>(
+    name: &'static str,
+    http: &Client,
+    config: &Config,
+    path: &str,
+    params: &HashMap<&str, &str>,
+) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let res = http
+        .post(config.url(path))
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| e.context(ErrorKind::FailedToSendRequest))?;
+
+    read_response(name, config, res).await
+}
+
+/// Posts `json` as body; the access token goes into the `Authorization` header since there is no form to carry it
+pub(crate) async fn api_call_json<T>(
+    name: &'static str,
+    http: &Client,
+    config: &Config,
+    path: &str,
+    json: &Value,
+    access_token: &str,
+) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let res = http
+        .post(config.url(path))
+        .bearer_auth(access_token)
+        .header(CONTENT_TYPE, "application/json")
+        .body(json.to_string())
+        .send()
+        .await
+        .map_err(|e| e.context(ErrorKind::FailedToSendRequest))?;
+
+    read_response(name, config, res).await
+}
+
+async fn read_response<T>(name: &'static str, config: &Config, res: Response) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let status = res.status();
+    let content_type = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(str::to_string);
+    let body = res
+        .bytes()
+        .await
+        .map_err(|e| e.context(ErrorKind::FailedToReadResponse))?;
+
+    T::from_http_response(config, name, status, content_type.as_deref(), &body)
+}
+
+impl AsyncNetatmo for AsyncAuthenticatedClient {
+    async fn add_webhook(&self, parameters: &add_webhook::Parameters<'_>) -> Result<add_webhook::Response> {
+        add_webhook::add_webhook_async(self, parameters).await
+    }
+
+    async fn drop_webhook(&self, parameters: &drop_webhook::Parameters) -> Result<drop_webhook::Response> {
+        drop_webhook::drop_webhook_async(self, parameters).await
+    }
+
+    async fn get_camera_picture(&self, parameters: &get_camera_picture::Parameters<'_>) -> Result<Picture> {
+        get_camera_picture::get_camera_picture_async(self, parameters).await
+    }
+
+    async fn download_camera_picture(
+        &self,
+        parameters: &get_camera_picture::Parameters<'_>,
+        path: &Path,
+    ) -> Result<String> {
+        let picture = get_camera_picture::get_camera_picture_async(self, parameters).await?;
+        let kind = ErrorKind::FailedToWriteFile {
+            path: path.display().to_string(),
+        };
+        let path = path.to_path_buf();
+        run_blocking(kind, move || picture.save(path).map(str::to_string)).await?
+    }
+
+    async fn get_events(&self, parameters: &get_events::Parameters<'_>) -> Result<Events> {
+        get_events::get_events_async(self, parameters).await
+    }
+
+    async fn get_events_until(&self, parameters: &get_events_until::Parameters<'_>) -> Result<EventsList> {
+        get_events_until::get_events_until_async(self, parameters).await
+    }
+
+    async fn get_home_data(&self, parameters: &get_home_data::Parameters<'_>) -> Result<HomeData> {
+        get_home_data::get_home_data_async(self, parameters).await
+    }
+
+    async fn get_home_status(&self, parameters: &get_home_status::Parameters<'_>) -> Result<HomeStatus> {
+        get_home_status::get_home_status_async(self, parameters).await
+    }
+
+    async fn get_homes_data(&self, parameters: &get_homes_data::Parameters<'_>) -> Result<HomesData> {
+        get_homes_data::get_homes_data_async(self, parameters).await
+    }
+
+    async fn get_station_data(&self, device_id: &str) -> Result<StationData> {
+        get_station_data::get_station_data_async(self, device_id).await
+    }
+
+    async fn get_homecoachs_data(&self, device_id: &str) -> Result<StationData> {
+        get_station_data::get_homecoachs_data_async(self, device_id).await
+    }
+
+    async fn get_last_event_of(&self, parameters: &get_last_event_of::Parameters<'_>) -> Result<EventsList> {
+        get_last_event_of::get_last_event_of_async(self, parameters).await
+    }
+
+    async fn get_measure(&self, parameters: &get_measure::Parameters<'_>) -> Result<Measure> {
+        get_measure::get_measure_async(self, parameters).await
+    }
+
+    async fn get_room_measure(&self, parameters: &get_room_measure::Parameters<'_>) -> Result<RoomMeasure> {
+        get_room_measure::get_room_measure_async(self, parameters).await
+    }
+
+    async fn get_next_events(&self, parameters: &get_next_events::Parameters<'_>) -> Result<EventsList> {
+        get_next_events::get_next_events_async(self, parameters).await
+    }
+
+    async fn get_public_data(&self, parameters: &get_public_data::Parameters<'_>) -> Result<PublicData> {
+        get_public_data::get_public_data_async(self, parameters).await
+    }
+
+    async fn set_persons_away(
+        &self,
+        parameters: &set_persons_away::Parameters<'_>,
+    ) -> Result<set_persons_away::Response> {
+        set_persons_away::set_persons_away_async(self, parameters).await
+    }
+
+    async fn set_persons_home(
+        &self,
+        parameters: &set_persons_home::Parameters<'_>,
+    ) -> Result<set_persons_home::Response> {
+        set_persons_home::set_persons_home_async(self, parameters).await
+    }
+
+    async fn set_room_thermpoint(
+        &self,
+        parameters: &set_room_thermpoint::Parameters<'_>,
+    ) -> Result<set_room_thermpoint::Response> {
+        set_room_thermpoint::set_room_thermpoint_async(self, parameters).await
+    }
+
+    async fn set_therm_mode(&self, parameters: &set_therm_mode::Parameters<'_>) -> Result<set_therm_mode::Response> {
+        set_therm_mode::set_therm_mode_async(self, parameters).await
+    }
+
+    async fn create_new_home_schedule(
+        &self,
+        parameters: &create_new_home_schedule::Parameters<'_>,
+    ) -> Result<create_new_home_schedule::Response> {
+        create_new_home_schedule::create_new_home_schedule_async(self, parameters).await
+    }
+
+    async fn delete_home_schedule(
+        &self,
+        parameters: &delete_home_schedule::Parameters<'_>,
+    ) -> Result<delete_home_schedule::Response> {
+        delete_home_schedule::delete_home_schedule_async(self, parameters).await
+    }
+
+    async fn rename_home_schedule(
+        &self,
+        parameters: &rename_home_schedule::Parameters<'_>,
+    ) -> Result<rename_home_schedule::Response> {
+        rename_home_schedule::rename_home_schedule_async(self, parameters).await
+    }
+
+    async fn switch_home_schedule(
+        &self,
+        parameters: &switch_home_schedule::Parameters<'_>,
+    ) -> Result<switch_home_schedule::Response> {
+        switch_home_schedule::switch_home_schedule_async(self, parameters).await
+    }
+
+    async fn sync_home_schedule(
+        &self,
+        parameters: &sync_home_schedule::Parameters<'_>,
+    ) -> Result<sync_home_schedule::Response> {
+        sync_home_schedule::sync_home_schedule_async(self, parameters).await
+    }
+
+    async fn set_state(&self, parameters: &set_state::Parameters<'_>) -> Result<set_state::Response> {
+        set_state::set_state_async(self, parameters).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::{
+        local_http::FakeServer,
+        picture_cache::PictureCache,
+        test_support::{async_client, now, token, EXPIRED_TOKEN_ERROR, REFRESHED_TOKEN, RESPONSE},
+        token_store::MemoryTokenStore,
+        NetatmoClient,
+    };
+
+    fn client(server: &FakeServer) -> AsyncAuthenticatedClient {
+        async_client(server, token(now()))
+    }
+
+    #[tokio::test]
+    async fn call_endpoint() {
+        let server = FakeServer::start(vec![(200, RESPONSE)]);
+        let client = client(&server);
+        let parameters = set_room_thermpoint::Parameters::new("home", "room", set_room_thermpoint::Mode::Home);
+
+        let res = client.set_room_thermpoint(&parameters).await;
+
+        assert_that(&res).is_ok();
+        assert_that(&server.requests()[0].target).is_equal_to("/api/setroomthermpoint".to_string());
+    }
+
+    #[tokio::test]
+    async fn refresh_token_after_expired_token_error() {
+        let server = FakeServer::start(vec![
+            (403, EXPIRED_TOKEN_ERROR),
+            (200, REFRESHED_TOKEN),
+            (200, RESPONSE),
+        ]);
+        let client = client(&server);
+        let parameters = set_room_thermpoint::Parameters::new("home", "room", set_room_thermpoint::Mode::Home);
+
+        let res = client.set_room_thermpoint(&parameters).await;
+
+        assert_that(&res).is_ok();
+        assert_that(&client.token().await.access_token).is_equal_to("abcdef|refreshed".to_string());
+        assert_that(&server.requests()[1].target).is_equal_to("/oauth2/token".to_string());
+    }
+
+    #[tokio::test]
+    async fn save_refreshed_token_to_token_store() {
+        let server = FakeServer::start(vec![
+            (403, EXPIRED_TOKEN_ERROR),
+            (200, REFRESHED_TOKEN),
+            (200, RESPONSE),
+        ]);
+        let token_store = Arc::new(MemoryTokenStore::new());
+        let client = client(&server)
+            .with_token_store(token_store.clone())
+            .expect("Failed to save token");
+        let parameters = set_room_thermpoint::Parameters::new("home", "room", set_room_thermpoint::Mode::Home);
+
+        let res = client.set_room_thermpoint(&parameters).await;
+
+        assert_that(&res).is_ok();
+        assert_that(
+            &token_store
+                .load()
+                .expect("Failed to load token")
+                .map(|x| x.access_token),
+        )
+        .is_some()
+        .is_equal_to("abcdef|refreshed".to_string());
+    }
+
+    #[tokio::test]
+    async fn download_picture_through_cache() {
+        let server = FakeServer::start(vec![(200, "GIF89a")]);
+        let dir = std::env::temp_dir().join(format!("netatmo-rs-test-async-picture-cache-{}", std::process::id()));
+        let path = dir.with_extension("gif");
+        let _ = std::fs::remove_dir_all(&dir);
+        let client = NetatmoClient::builder()
+            .base_url(server.url())
+            .picture_cache(PictureCache::new(&dir))
+            .build_async_with_token(token(now()))
+            .expect("Failed to build client");
+        let parameters = get_camera_picture::Parameters::new("5e2f0003", "g7h8i9");
+
+        let downloaded = client.download_camera_picture(&parameters, &path).await;
+        let cached = client.get_camera_picture(&parameters).await;
+
+        assert_that(&downloaded).is_ok().is_equal_to("image/gif".to_string());
+        assert_that(&cached.map(|x| x.bytes))
+            .is_ok()
+            .is_equal_to(b"GIF89a".to_vec());
+        assert_that(&std::fs::read(&path).ok())
+            .is_some()
+            .is_equal_to(b"GIF89a".to_vec());
+        assert_that(&server.requests()).has_length(1);
+    }
+
+    #[tokio::test]
+    async fn keep_newer_token_of_token_store() {
+        let server = FakeServer::start(vec![]);
+        let token_store = Arc::new(MemoryTokenStore::new());
+        let client = client(&server);
+        let newer = Token {
+            access_token: "abcdef|newer".to_string(),
+            issued_at: client.token().await.issued_at + 1,
+            ..client.token().await
+        };
+        token_store.save(&newer).expect("Failed to save token");
+
+        let client = client
+            .with_token_store(token_store.clone())
+            .expect("Failed to sync token");
+
+        assert_that(&client.token().await.access_token).is_equal_to("abcdef|newer".to_string());
+        assert_that(
+            &token_store
+                .load()
+                .expect("Failed to load token")
+                .map(|x| x.access_token),
+        )
+        .is_some()
+        .is_equal_to("abcdef|newer".to_string());
+    }
+}
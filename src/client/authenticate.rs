@@ -1,29 +1,71 @@
-use crate::{client::UnauthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::{asynchronous, AsyncUnauthenticatedClient};
+#[cfg(feature = "blocking")]
+use crate::client::{blocking, UnauthenticatedClient};
+use crate::{
+    client::{response::ApiResponse, ClientCredentials, Config},
+    errors::Result,
+};
 
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-#[derive(Debug, Serialize, Deserialize)]
+pub(crate) const TOKEN_PATH: &str = "oauth2/token";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub access_token: String,
     pub refresh_token: String,
+    #[serde(default)]
     pub scope: Vec<Scope>,
     pub expires_in: u64,
     pub expire_in: u64,
+    /// Unix timestamp in seconds when this token has been issued; set to the time of receipt if the response lacks it.
+    #[serde(default = "unix_timestamp")]
+    pub issued_at: u64,
+}
+
+impl ApiResponse for Token {}
+
+impl Token {
+    /// Unix timestamp in seconds when this token expires
+    pub fn expires_at(&self) -> u64 {
+        self.issued_at.saturating_add(self.expires_in)
+    }
+
+    /// Checks if this token has expired or is going to expire within `duration`
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        unix_timestamp().saturating_add(duration.as_secs()) >= self.expires_at()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::from_secs(0))
+    }
+}
+
+pub(crate) fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
 }
 
 #[allow(clippy::implicit_hasher)]
-impl<'a> From<&'a UnauthenticatedClient<'a>> for HashMap<&str, &'a str> {
-    fn from(uc: &'a UnauthenticatedClient) -> HashMap<&'static str, &'a str> {
+impl<'a> From<&'a ClientCredentials<'a>> for HashMap<&str, &'a str> {
+    fn from(cc: &'a ClientCredentials) -> HashMap<&'static str, &'a str> {
         let mut m = HashMap::default();
-        m.insert("client_id", uc.client_credentials.client_id);
-        m.insert("client_secret", uc.client_credentials.client_secret);
+        m.insert("client_id", cc.client_id);
+        m.insert("client_secret", cc.client_secret);
 
         m
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Scope {
     ReadStation,
@@ -55,7 +97,7 @@ impl fmt::Display for Scope {
 }
 
 impl Scope {
-    fn to_scope_str(&self) -> &'static str {
+    pub(crate) fn to_scope_str(&self) -> &'static str {
         match self {
             Scope::ReadStation => "read_station",
             Scope::ReadThermostat => "read_thermostat",
@@ -70,24 +112,182 @@ impl Scope {
     }
 }
 
-pub(crate) fn get_token(
-    unauthenticated_client: &UnauthenticatedClient,
-    username: &str,
-    password: &str,
-    scopes: &[Scope],
-) -> Result<Token> {
-    let scopes_str: String = scopes
+fn scopes_str(scopes: &[Scope]) -> String {
+    scopes
         .iter()
         .map(Scope::to_scope_str)
         .collect::<Vec<_>>()
         .as_slice()
-        .join(".");
+        .join(".")
+}
 
-    let mut params: HashMap<_, _> = unauthenticated_client.into();
+fn password_params<'a>(
+    client_credentials: &'a ClientCredentials,
+    username: &'a str,
+    password: &'a str,
+    scopes_str: &'a str,
+) -> HashMap<&'static str, &'a str> {
+    let mut params: HashMap<_, _> = client_credentials.into();
     params.insert("username", username);
     params.insert("password", password);
     params.insert("grant_type", "password");
-    params.insert("scope", &scopes_str);
+    params.insert("scope", scopes_str);
+
+    params
+}
+
+fn refresh_params<'a>(
+    client_credentials: &'a ClientCredentials,
+    refresh_token: &'a str,
+) -> HashMap<&'static str, &'a str> {
+    let mut params: HashMap<_, _> = client_credentials.into();
+    params.insert("grant_type", "refresh_token");
+    params.insert("refresh_token", refresh_token);
+
+    params
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn get_token(
+    unauthenticated_client: &UnauthenticatedClient,
+    username: &str,
+    password: &str,
+    scopes: &[Scope],
+) -> Result<Token> {
+    let scopes_str = scopes_str(scopes);
+    let params = password_params(
+        unauthenticated_client.client_credentials,
+        username,
+        password,
+        &scopes_str,
+    );
+
+    unauthenticated_client.call("oauth2/token", TOKEN_PATH, &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_token_async(
+    unauthenticated_client: &AsyncUnauthenticatedClient<'_>,
+    username: &str,
+    password: &str,
+    scopes: &[Scope],
+) -> Result<Token> {
+    let scopes_str = scopes_str(scopes);
+    let params = password_params(
+        unauthenticated_client.client_credentials,
+        username,
+        password,
+        &scopes_str,
+    );
+
+    unauthenticated_client.call("oauth2/token", TOKEN_PATH, &params).await
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn refresh_token(
+    http: &reqwest::blocking::Client,
+    config: &Config,
+    client_credentials: &ClientCredentials,
+    refresh_token: &str,
+) -> Result<Token> {
+    let params = refresh_params(client_credentials, refresh_token);
+
+    blocking::api_call("oauth2/token", http, config, TOKEN_PATH, &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn refresh_token_async(
+    http: &reqwest::Client,
+    config: &Config,
+    client_credentials: &ClientCredentials<'_>,
+    refresh_token: &str,
+) -> Result<Token> {
+    let params = refresh_params(client_credentials, refresh_token);
+
+    asynchronous::api_call("oauth2/token", http, config, TOKEN_PATH, &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod token {
+        use super::*;
+
+        #[test]
+        fn parse_response_sets_issued_at() {
+            let json = r#"{
+  "access_token": "abcdef|123456",
+  "refresh_token": "abcdef|654321",
+  "scope": ["read_station"],
+  "expires_in": 10800,
+  "expire_in": 10800
+}"#;
 
-    unauthenticated_client.call("oauth2/token", "https://api.netatmo.com/oauth2/token", &params)
+            let token: Token = serde_json::from_str(json).expect("Failed to parse token");
+
+            assert_that(&token.issued_at).is_greater_than(0);
+            assert_that(&token.expires_at()).is_equal_to(token.issued_at + 10800);
+            assert_that(&token.is_expired()).is_false();
+        }
+
+        #[test]
+        fn expires_within() {
+            let token = Token {
+                access_token: "abcdef|123456".to_string(),
+                refresh_token: "abcdef|654321".to_string(),
+                scope: vec![Scope::ReadStation],
+                expires_in: 10800,
+                expire_in: 10800,
+                issued_at: unix_timestamp() - 10800 + 30,
+            };
+
+            assert_that(&token.is_expired()).is_false();
+            assert_that(&token.expires_within(Duration::from_secs(60))).is_true();
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod refresh {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{
+                client, form, now, set_room_thermpoint, token, EXPIRED_TOKEN_ERROR, REFRESHED_TOKEN, RESPONSE,
+            },
+        };
+
+        #[test]
+        fn refresh_expiring_token_before_call() {
+            let server = FakeServer::start(vec![(200, REFRESHED_TOKEN), (200, RESPONSE)]);
+            let client = client(&server, token(now() - 10800));
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            let requests = server.requests();
+            assert_that(&requests[0].target).is_equal_to("/oauth2/token".to_string());
+            assert_that(&form(&requests[0])["grant_type"]).is_equal_to("refresh_token".to_string());
+            assert_that(&form(&requests[1])["access_token"]).is_equal_to("abcdef|refreshed".to_string());
+        }
+
+        #[test]
+        fn refresh_token_after_expired_token_error() {
+            let server = FakeServer::start(vec![
+                (403, EXPIRED_TOKEN_ERROR),
+                (200, REFRESHED_TOKEN),
+                (200, RESPONSE),
+            ]);
+            let client = client(&server, token(now()));
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            let requests = server.requests();
+            assert_that(&form(&requests[0])["access_token"]).is_equal_to("abcdef|123456".to_string());
+            assert_that(&form(&requests[2])["access_token"]).is_equal_to("abcdef|refreshed".to_string());
+        }
+    }
 }
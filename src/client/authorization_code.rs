@@ -0,0 +1,350 @@
+#[cfg(feature = "async")]
+use crate::client::{AsyncAuthenticatedClient, AsyncUnauthenticatedClient};
+#[cfg(feature = "blocking")]
+use crate::client::{AuthenticatedClient, UnauthenticatedClient};
+use crate::{
+    client::{
+        authenticate::{Scope, Token, TOKEN_PATH},
+        local_http, ClientCredentials, Config,
+    },
+    errors::{Error, ErrorKind, Result},
+};
+
+use failure::Fail;
+use log::{debug, warn};
+use reqwest::Url;
+use std::{
+    collections::HashMap,
+    net::{TcpListener, ToSocketAddrs},
+};
+
+const AUTHORIZE_PATH: &str = "oauth2/authorize";
+
+const SUCCESS_PAGE: &str = "<html><body>Authorization complete. You may close this window now.</body></html>";
+const FAILURE_PAGE: &str = "<html><body>Authorization failed. Please check the application's log.</body></html>";
+
+const STATE_LENGTH: usize = 16;
+
+/// OAuth2 authorization code grant for the blocking or the async unauthenticated client `C`
+///
+/// cf. https://dev.netatmo.com/apidocumentation/oauth#authorization-code
+pub struct AuthorizationCodeFlow<C> {
+    client: C,
+    authorize_url: String,
+    client_id: String,
+    redirect_uri: String,
+    scopes: Vec<Scope>,
+    state: String,
+}
+
+impl<C> AuthorizationCodeFlow<C> {
+    pub(crate) fn new(client: C, client_id: &str, config: &Config, redirect_uri: &str, scopes: &[Scope]) -> Self {
+        AuthorizationCodeFlow {
+            client,
+            authorize_url: config.url(AUTHORIZE_PATH),
+            client_id: client_id.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            scopes: scopes.to_vec(),
+            state: random_state(),
+        }
+    }
+
+    /// Overrides the randomly generated `state` value
+    pub fn state(self, state: &str) -> Self {
+        AuthorizationCodeFlow {
+            state: state.to_string(),
+            ..self
+        }
+    }
+
+    pub fn get_state(&self) -> &str {
+        &self.state
+    }
+
+    /// The URL the user has to open in a browser to grant access
+    pub fn authorize_url(&self) -> String {
+        let scopes_str = self.scopes_str();
+        let params = [
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", scopes_str.as_str()),
+            ("state", self.state.as_str()),
+        ];
+        Url::parse_with_params(&self.authorize_url, &params)
+            .expect("authorize URL is valid")
+            .to_string()
+    }
+
+    fn token_params<'a>(
+        &'a self,
+        client_credentials: &'a ClientCredentials,
+        code: &'a str,
+        scopes_str: &'a str,
+    ) -> HashMap<&'static str, &'a str> {
+        let mut params: HashMap<_, _> = client_credentials.into();
+        params.insert("grant_type", "authorization_code");
+        params.insert("code", code);
+        params.insert("redirect_uri", &self.redirect_uri);
+        params.insert("scope", scopes_str);
+
+        params
+    }
+
+    fn scopes_str(&self) -> String {
+        self.scopes
+            .iter()
+            .map(Scope::to_scope_str)
+            .collect::<Vec<_>>()
+            .as_slice()
+            .join(" ")
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> AuthorizationCodeFlow<UnauthenticatedClient<'a>> {
+    /// Waits for the redirect on `listener`, checks its `state` and exchanges the code for a token
+    pub fn authenticate(self, listener: &RedirectListener) -> Result<AuthenticatedClient> {
+        let code = listener.wait_for_code(&self.state)?;
+        self.exchange_code(&code)
+    }
+
+    /// Exchanges an authorization code for a token
+    pub fn exchange_code(self, code: &str) -> Result<AuthenticatedClient> {
+        let scopes_str = self.scopes_str();
+        let params = self.token_params(self.client.client_credentials, code, &scopes_str);
+        let token: Token = self
+            .client
+            .call("oauth2/token", TOKEN_PATH, &params)
+            .map_err(|e| e.context(ErrorKind::AuthenticationFailed))?;
+
+        Ok(self.client.into_authenticated(token))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a> AuthorizationCodeFlow<AsyncUnauthenticatedClient<'a>> {
+    /// Exchanges an authorization code for a token
+    ///
+    /// Receive the code with `RedirectListener::wait_for_code`, which blocks the current thread.
+    pub async fn exchange_code(self, code: &str) -> Result<AsyncAuthenticatedClient> {
+        let scopes_str = self.scopes_str();
+        let params = self.token_params(self.client.client_credentials, code, &scopes_str);
+        let token: Token = self
+            .client
+            .call("oauth2/token", TOKEN_PATH, &params)
+            .await
+            .map_err(|e| e.context(ErrorKind::AuthenticationFailed))?;
+
+        Ok(self.client.into_authenticated(token))
+    }
+}
+
+/// Loopback HTTP listener catching the redirect of the authorization code grant
+pub struct RedirectListener {
+    listener: TcpListener,
+    redirect_uri: String,
+}
+
+impl RedirectListener {
+    /// Binds to `addr`; the redirect URI is `http://<local address>/`
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<RedirectListener> {
+        let listener = TcpListener::bind(addr).map_err(|e| e.context(ErrorKind::RedirectListenerFailed))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| e.context(ErrorKind::RedirectListenerFailed))?;
+        let redirect_uri = format!("http://{}/", local_addr);
+
+        Ok(RedirectListener { listener, redirect_uri })
+    }
+
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    /// Blocks until a redirect carrying `state` arrives and returns its code or error
+    ///
+    /// Broken or idle connections and redirects with a missing or different `state` are skipped; only failures of the
+    /// listener itself end the wait.
+    pub fn wait_for_code(&self, state: &str) -> Result<String> {
+        loop {
+            let (mut stream, peer) = match local_http::accept(&self.listener, local_http::READ_TIMEOUT)
+                .map_err(|e| e.context(ErrorKind::RedirectListenerFailed))?
+            {
+                Some(accepted) => accepted,
+                None => continue,
+            };
+            let request = match local_http::read_request(&mut stream) {
+                Ok(request) => request,
+                Err(e) => {
+                    warn!("Failed to read redirect request from {}: {}", peer, e);
+                    let _ = local_http::write_response(&mut stream, 400, "text/html", b"");
+                    continue;
+                }
+            };
+            debug!("Received '{} {}' from {}", request.method, request.target, peer);
+
+            let query: HashMap<String, String> =
+                match Url::parse("http://localhost").and_then(|base| base.join(&request.target)) {
+                    Ok(url) => url.query_pairs().into_owned().collect(),
+                    Err(_) => HashMap::new(),
+                };
+            if !query.contains_key("code") && !query.contains_key("error") {
+                // Browsers also ask for things like the favicon; keep waiting for the actual redirect.
+                let _ = local_http::write_response(&mut stream, 404, "text/html", b"");
+                continue;
+            }
+
+            if query.get("state").map(String::as_str) != Some(state) {
+                // Anyone on this host may connect; only the redirect carrying our state ends the flow.
+                warn!(
+                    "Ignoring redirect from {}: {}",
+                    peer,
+                    ErrorKind::AuthorizationStateMismatch
+                );
+                let _ = local_http::write_response(&mut stream, 200, "text/html", FAILURE_PAGE.as_bytes());
+                continue;
+            }
+
+            let res = check_redirect(&query);
+            let page = if res.is_ok() { SUCCESS_PAGE } else { FAILURE_PAGE };
+            let _ = local_http::write_response(&mut stream, 200, "text/html", page.as_bytes());
+
+            return res;
+        }
+    }
+}
+
+fn check_redirect(query: &HashMap<String, String>) -> Result<String> {
+    if let Some(reason) = query.get("error") {
+        return Err(Error::from(ErrorKind::AuthorizationDenied {
+            reason: reason.to_string(),
+        }));
+    }
+    query
+        .get("code")
+        .cloned()
+        .ok_or_else(|| Error::from(ErrorKind::RedirectListenerFailed))
+}
+
+fn random_state() -> String {
+    let mut bytes = [0u8; STATE_LENGTH];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator is available");
+
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::{local_http::FakeServer, NetatmoClient};
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        thread,
+    };
+
+    const TOKEN: &str = r#"{
+  "access_token": "abcdef|123456",
+  "refresh_token": "abcdef|654321",
+  "scope": ["read_station", "read_thermostat"],
+  "expires_in": 10800,
+  "expire_in": 10800
+}"#;
+
+    fn redirect(redirect_uri: &str, queries: &[&str]) {
+        let addr = redirect_uri
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+            .to_string();
+        let targets: Vec<String> = std::iter::once("/favicon.ico".to_string())
+            .chain(queries.iter().map(|query| format!("/?{}", query)))
+            .collect();
+        thread::spawn(move || {
+            for target in &targets {
+                let mut stream = TcpStream::connect(&addr).expect("Failed to connect to redirect listener");
+                write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).expect("Failed to write");
+                let _ = stream.read_to_end(&mut Vec::new());
+            }
+        });
+    }
+
+    #[test]
+    fn authorize_url() {
+        let client_credentials = ClientCredentials {
+            client_id: "my_client_id",
+            client_secret: "my_client_secret",
+        };
+        let flow = NetatmoClient::new(&client_credentials)
+            .authorization_code_flow("http://127.0.0.1:8080/", &[Scope::ReadStation, Scope::ReadThermostat])
+            .state("my_state");
+
+        assert_that(&flow.authorize_url()).is_equal_to(
+            "https://api.netatmo.com/oauth2/authorize?client_id=my_client_id&redirect_uri=http%3A%2F%2F127.0.0.1%3A8080%2F&scope=read_station+read_thermostat&state=my_state".to_string(),
+        );
+    }
+
+    #[test]
+    fn authenticate_with_redirect() {
+        let client_credentials = ClientCredentials {
+            client_id: "my_client_id",
+            client_secret: "my_client_secret",
+        };
+        let server = FakeServer::start(vec![(200, TOKEN)]);
+        let listener = RedirectListener::bind("127.0.0.1:0").expect("Failed to bind redirect listener");
+        let flow = NetatmoClient::builder()
+            .base_url(server.url())
+            .build(&client_credentials)
+            .expect("Failed to build client")
+            .authorization_code_flow(listener.redirect_uri(), &[Scope::ReadStation])
+            .state("my_state");
+
+        redirect(listener.redirect_uri(), &["state=my_state&code=my_code"]);
+        let client = flow.authenticate(&listener).expect("Failed to authenticate");
+
+        assert_that(&client.token().access_token).is_equal_to("abcdef|123456".to_string());
+        let requests = server.requests();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert_that(&body.contains("grant_type=authorization_code")).is_true();
+        assert_that(&body.contains("code=my_code")).is_true();
+    }
+
+    #[test]
+    fn ignore_forged_redirects() {
+        let listener = RedirectListener::bind("127.0.0.1:0").expect("Failed to bind redirect listener");
+
+        redirect(
+            listener.redirect_uri(),
+            &[
+                "error=access_denied",
+                "state=forged_state&code=forged_code",
+                "state=my_state&code=my_code",
+            ],
+        );
+        let res = listener.wait_for_code("my_state");
+
+        assert_that(&res.map_err(|e| e.kind().clone())).is_ok_containing("my_code".to_string());
+    }
+
+    #[test]
+    fn random_state_is_hex_encoded() {
+        let state = random_state();
+
+        assert_that(&state.len()).is_equal_to(2 * STATE_LENGTH);
+        assert_that(&state.chars().all(|c| c.is_ascii_hexdigit())).is_true();
+        assert_that(&state).is_not_equal_to(random_state());
+    }
+
+    #[test]
+    fn reject_denied_authorization() {
+        let listener = RedirectListener::bind("127.0.0.1:0").expect("Failed to bind redirect listener");
+
+        redirect(listener.redirect_uri(), &["state=my_state&error=access_denied"]);
+        let res = listener.wait_for_code("my_state");
+
+        assert_that(&res.map_err(|e| e.kind().clone())).is_err_containing(ErrorKind::AuthorizationDenied {
+            reason: "access_denied".to_string(),
+        });
+    }
+}
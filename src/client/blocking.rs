@@ -0,0 +1,521 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+use failure::Fail;
+use log::{debug, warn};
+use reqwest::{
+    blocking::{Client, Response},
+    header::CONTENT_TYPE,
+};
+use serde_json::Value;
+
+use crate::{
+    client::{
+        add_webhook,
+        authenticate::{self, Scope, Token},
+        authorization_code::AuthorizationCodeFlow,
+        create_new_home_schedule, delete_home_schedule, drop_webhook,
+        get_camera_picture::{self, Picture},
+        get_events::{self, Events, EventsList},
+        get_events_until,
+        get_home_data::{self, HomeData},
+        get_home_status::{self, HomeStatus},
+        get_homes_data::{self, HomesData},
+        get_last_event_of,
+        get_measure::{self, Measure},
+        get_next_events,
+        get_public_data::{self, PublicData},
+        get_room_measure::{self, RoomMeasure},
+        get_station_data::{self, StationData},
+        is_expired_token_error, is_rate_limit_error, newer_stored_token,
+        picture_cache::PictureCache,
+        rate_limit::RateLimiter,
+        rename_home_schedule, save_refreshed_token, set_persons_away, set_persons_home, set_room_thermpoint, set_state,
+        set_therm_mode, switch_home_schedule, sync_home_schedule, sync_with_token_store,
+        token_store::TokenStore,
+        ClientCredentials, Config, FromHttpResponse, OwnedClientCredentials, RequestBody, TOKEN_REFRESH_MARGIN,
+    },
+    errors::{Error, ErrorKind, Result},
+};
+
+pub trait Netatmo {
+    fn add_webhook(&self, parameters: &add_webhook::Parameters) -> Result<add_webhook::Response>;
+    fn drop_webhook(&self, parameters: &drop_webhook::Parameters) -> Result<drop_webhook::Response>;
+    fn get_camera_picture(&self, parameters: &get_camera_picture::Parameters) -> Result<Picture>;
+    /// Downloads a camera picture to `path` and returns its content type
+    fn download_camera_picture(&self, parameters: &get_camera_picture::Parameters, path: &Path) -> Result<String>;
+    fn get_events(&self, parameters: &get_events::Parameters) -> Result<Events>;
+    fn get_events_until(&self, parameters: &get_events_until::Parameters) -> Result<EventsList>;
+    fn get_home_data(&self, parameters: &get_home_data::Parameters) -> Result<HomeData>;
+    fn get_home_status(&self, parameters: &get_home_status::Parameters) -> Result<HomeStatus>;
+    fn get_homes_data(&self, parameters: &get_homes_data::Parameters) -> Result<HomesData>;
+    fn get_station_data(&self, device_id: &str) -> Result<StationData>;
+    fn get_homecoachs_data(&self, device_id: &str) -> Result<StationData>;
+    fn get_last_event_of(&self, parameters: &get_last_event_of::Parameters) -> Result<EventsList>;
+    fn get_measure(&self, parameters: &get_measure::Parameters) -> Result<Measure>;
+    fn get_room_measure(&self, parameters: &get_room_measure::Parameters) -> Result<RoomMeasure>;
+    fn get_next_events(&self, parameters: &get_next_events::Parameters) -> Result<EventsList>;
+    fn get_public_data(&self, parameters: &get_public_data::Parameters) -> Result<PublicData>;
+    fn set_persons_away(&self, parameters: &set_persons_away::Parameters) -> Result<set_persons_away::Response>;
+    fn set_persons_home(&self, parameters: &set_persons_home::Parameters) -> Result<set_persons_home::Response>;
+    fn set_room_thermpoint(
+        &self,
+        parameters: &set_room_thermpoint::Parameters,
+    ) -> Result<set_room_thermpoint::Response>;
+    fn set_therm_mode(&self, parameters: &set_therm_mode::Parameters) -> Result<set_therm_mode::Response>;
+    fn create_new_home_schedule(
+        &self,
+        parameters: &create_new_home_schedule::Parameters,
+    ) -> Result<create_new_home_schedule::Response>;
+    fn delete_home_schedule(
+        &self,
+        parameters: &delete_home_schedule::Parameters,
+    ) -> Result<delete_home_schedule::Response>;
+    fn rename_home_schedule(
+        &self,
+        parameters: &rename_home_schedule::Parameters,
+    ) -> Result<rename_home_schedule::Response>;
+    fn switch_home_schedule(
+        &self,
+        parameters: &switch_home_schedule::Parameters,
+    ) -> Result<switch_home_schedule::Response>;
+    fn sync_home_schedule(&self, parameters: &sync_home_schedule::Parameters) -> Result<sync_home_schedule::Response>;
+    fn set_state(&self, parameters: &set_state::Parameters) -> Result<set_state::Response>;
+}
+
+#[derive(Debug)]
+pub struct UnauthenticatedClient<'a> {
+    pub(crate) client_credentials: &'a ClientCredentials<'a>,
+    http: Client,
+    pub(crate) config: Config,
+}
+
+impl<'a> UnauthenticatedClient<'a> {
+    pub(crate) fn new(client_credentials: &'a ClientCredentials, http: Client, config: Config) -> Self {
+        UnauthenticatedClient {
+            client_credentials,
+            http,
+            config,
+        }
+    }
+
+    pub fn authenticate(self, username: &'a str, password: &'a str, scopes: &[Scope]) -> Result<AuthenticatedClient> {
+        authenticate::get_token(&self, username, password, scopes)
+            .map(|token| self.into_authenticated(token))
+            .map_err(|e| e.context(ErrorKind::AuthenticationFailed).into())
+    }
+
+    /// Starts an OAuth2 authorization code grant redirecting to `redirect_uri`
+    ///
+    /// Use `authorization_code::RedirectListener` to receive the redirect on a local port.
+    pub fn authorization_code_flow(self, redirect_uri: &str, scopes: &[Scope]) -> AuthorizationCodeFlow<Self> {
+        let client_id = self.client_credentials.client_id.to_string();
+        let config = self.config.clone();
+        AuthorizationCodeFlow::new(self, &client_id, &config, redirect_uri, scopes)
+    }
+
+    pub(crate) fn into_authenticated(self, token: Token) -> AuthenticatedClient {
+        AuthenticatedClient::new(token, self.http, self.config).with_client_credentials(self.client_credentials)
+    }
+
+    pub(crate) fn call<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        api_call(name, &self.http, &self.config, path, params)
+    }
+}
+
+pub struct AuthenticatedClient {
+    token: Mutex<Token>,
+    client_credentials: Option<OwnedClientCredentials>,
+    token_store: Option<Box<dyn TokenStore>>,
+    http: Client,
+    config: Config,
+}
+
+impl AuthenticatedClient {
+    pub(crate) fn new(token: Token, http: Client, config: Config) -> Self {
+        AuthenticatedClient {
+            token: Mutex::new(token),
+            client_credentials: None,
+            token_store: None,
+            http,
+            config,
+        }
+    }
+
+    /// Enables automatic token refresh using these client credentials
+    pub fn with_client_credentials(self, client_credentials: &ClientCredentials) -> Self {
+        AuthenticatedClient {
+            client_credentials: Some(client_credentials.into()),
+            ..self
+        }
+    }
+
+    /// Saves the current token to `token_store` and keeps it up to date whenever the token gets refreshed
+    ///
+    /// If the store already holds a token issued later than the current one, the client switches to that token
+    /// instead. Before refreshing, the client checks the store for a newer token saved by another client sharing the
+    /// store.
+    pub fn with_token_store<S: TokenStore + 'static>(mut self, token_store: S) -> Result<Self> {
+        sync_with_token_store(
+            &token_store,
+            self.token.get_mut().unwrap_or_else(PoisonError::into_inner),
+        )?;
+        Ok(AuthenticatedClient {
+            token_store: Some(Box::new(token_store)),
+            ..self
+        })
+    }
+
+    /// Returns a copy of the current token which changes whenever the token gets refreshed
+    pub fn token(&self) -> Token {
+        self.lock_token().clone()
+    }
+
+    /// Returns the rate limiter of this client to check the remaining budget
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.config.rate_limiter.as_deref()
+    }
+
+    /// Returns the picture cache of this client, e.g., to purge it
+    pub fn picture_cache(&self) -> Option<&PictureCache> {
+        self.config.picture_cache.as_ref()
+    }
+
+    /// Refreshes the access token using the refresh token
+    pub fn refresh_token(&self) -> Result<()> {
+        let mut token = self.lock_token();
+        self.refresh_locked_token(&mut token)
+    }
+
+    pub(crate) fn call<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Form(params), false)
+    }
+
+    /// Calls an endpoint that changes state and therefore is only retried if the retry policy allows writes
+    pub(crate) fn call_write<T>(&self, name: &'static str, path: &str, params: &HashMap<&str, &str>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Form(params), true)
+    }
+
+    /// Like `call_write`, but sends `json` as body
+    pub(crate) fn call_write_json<T>(&self, name: &'static str, path: &str, json: &Value) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.call_with_retry(name, path, RequestBody::Json(json), true)
+    }
+
+    fn call_with_retry<T>(&self, name: &'static str, path: &str, body: RequestBody<'_>, write: bool) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        let retry_policy = &self.config.retry_policy;
+        let mut attempt = 1;
+        loop {
+            match self.call_once(name, path, body) {
+                Err(ref e) if retry_policy.should_retry(e.kind(), attempt, write) => {
+                    let delay = retry_policy.delay(attempt);
+                    warn!(
+                        "Attempt {} of '{}' failed: {}; retrying in {:?}",
+                        attempt, name, e, delay
+                    );
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    fn call_once<T>(&self, name: &'static str, path: &str, body: RequestBody<'_>) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        let access_token = self.valid_access_token()?;
+        match self.call_with_access_token(name, path, body, &access_token) {
+            Err(ref e) if self.client_credentials.is_some() && is_expired_token_error(e) => {
+                debug!("Access token expired during '{}'; refreshing and retrying", name);
+                let access_token = self.refreshed_access_token(&access_token)?;
+                self.call_with_access_token(name, path, body, &access_token)
+            }
+            res => res,
+        }
+    }
+
+    fn call_with_access_token<T>(
+        &self,
+        name: &'static str,
+        path: &str,
+        body: RequestBody<'_>,
+        access_token: &str,
+    ) -> Result<T>
+    where
+        T: FromHttpResponse,
+    {
+        self.wait_for_rate_limit()?;
+        let res = match body {
+            RequestBody::Form(params) => {
+                let mut params = params.clone();
+                params.insert("access_token", access_token);
+                api_call(name, &self.http, &self.config, path, &params)
+            }
+            RequestBody::Json(json) => api_call_json(name, &self.http, &self.config, path, json, access_token),
+        };
+        if let (Err(ref e), Some(rate_limiter)) = (&res, &self.config.rate_limiter) {
+            if is_rate_limit_error(e) {
+                rate_limiter.exhaust();
+            }
+        }
+
+        res
+    }
+
+    fn wait_for_rate_limit(&self) -> Result<()> {
+        if let Some(ref rate_limiter) = self.config.rate_limiter {
+            while let Some(wait_time) = rate_limiter.acquire_or_wait_time()? {
+                debug!("Rate limit exhausted; waiting {:?}", wait_time);
+                thread::sleep(wait_time);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current access token after refreshing it if it is about to expire
+    fn valid_access_token(&self) -> Result<String> {
+        let mut token = self.lock_token();
+        if self.client_credentials.is_some() && token.expires_within(TOKEN_REFRESH_MARGIN) {
+            debug!("Access token expires at {}; refreshing", token.expires_at());
+            self.refresh_locked_token(&mut token)?;
+        }
+        Ok(token.access_token.clone())
+    }
+
+    /// Returns a refreshed access token unless another caller already replaced `expired_access_token`
+    fn refreshed_access_token(&self, expired_access_token: &str) -> Result<String> {
+        let mut token = self.lock_token();
+        if token.access_token == expired_access_token {
+            self.refresh_locked_token(&mut token)?;
+        }
+        Ok(token.access_token.clone())
+    }
+
+    fn refresh_locked_token(&self, token: &mut Token) -> Result<()> {
+        if let Some(stored) = newer_stored_token(self.token_store.as_deref(), token) {
+            debug!("Using newer token from token store");
+            *token = stored;
+            return Ok(());
+        }
+
+        let client_credentials = self
+            .client_credentials
+            .as_ref()
+            .ok_or_else(|| Error::from(ErrorKind::TokenRefreshFailed))?;
+        *token = authenticate::refresh_token(
+            &self.http,
+            &self.config,
+            &client_credentials.into(),
+            &token.refresh_token,
+        )
+        .map_err(|e| e.context(ErrorKind::TokenRefreshFailed))?;
+        save_refreshed_token(self.token_store.as_deref(), token);
+
+        Ok(())
+    }
+
+    fn lock_token(&self) -> MutexGuard<'_, Token> {
+        // A poisoned lock still holds a complete token because it is only ever replaced as a whole
+        self.token.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub(crate) fn api_call<T>(
+    name: &'static str,
+    http: &Client,
+    config: &Config,
+    path: &str,
+    params: &HashMap<&str, &str>,
+) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let res = http
+        .post(config.url(path))
+        .form(&params)
+        .send()
+        .map_err(|e| e.context(ErrorKind::FailedToSendRequest))?;
+
+    read_response(name, config, res)
+}
+
+/// Posts `json` as body; the access token goes into the `Authorization` header since there is no form to carry it
+pub(crate) fn api_call_json<T>(
+    name: &'static str,
+    http: &Client,
+    config: &Config,
+    path: &str,
+    json: &Value,
+    access_token: &str,
+) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let res = http
+        .post(config.url(path))
+        .bearer_auth(access_token)
+        .header(CONTENT_TYPE, "application/json")
+        .body(json.to_string())
+        .send()
+        .map_err(|e| e.context(ErrorKind::FailedToSendRequest))?;
+
+    read_response(name, config, res)
+}
+
+fn read_response<T>(name: &'static str, config: &Config, res: Response) -> Result<T>
+where
+    T: FromHttpResponse,
+{
+    let status = res.status();
+    let content_type = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(str::to_string);
+    let body = res.bytes().map_err(|e| e.context(ErrorKind::FailedToReadResponse))?;
+
+    T::from_http_response(config, name, status, content_type.as_deref(), &body)
+}
+
+impl Netatmo for AuthenticatedClient {
+    fn add_webhook(&self, parameters: &add_webhook::Parameters) -> Result<add_webhook::Response> {
+        add_webhook::add_webhook(self, parameters)
+    }
+
+    fn drop_webhook(&self, parameters: &drop_webhook::Parameters) -> Result<drop_webhook::Response> {
+        drop_webhook::drop_webhook(self, parameters)
+    }
+
+    fn get_camera_picture(&self, parameters: &get_camera_picture::Parameters) -> Result<Picture> {
+        get_camera_picture::get_camera_picture(self, parameters)
+    }
+
+    fn download_camera_picture(&self, parameters: &get_camera_picture::Parameters, path: &Path) -> Result<String> {
+        let picture = get_camera_picture::get_camera_picture(self, parameters)?;
+        picture.save(path).map(str::to_string)
+    }
+
+    fn get_events(&self, parameters: &get_events::Parameters) -> Result<Events> {
+        get_events::get_events(self, parameters)
+    }
+
+    fn get_events_until(&self, parameters: &get_events_until::Parameters) -> Result<EventsList> {
+        get_events_until::get_events_until(self, parameters)
+    }
+
+    fn get_home_data(&self, parameters: &get_home_data::Parameters) -> Result<HomeData> {
+        get_home_data::get_home_data(self, parameters)
+    }
+
+    fn get_homes_data(&self, parameters: &get_homes_data::Parameters) -> Result<HomesData> {
+        get_homes_data::get_homes_data(self, parameters)
+    }
+
+    fn get_home_status(&self, parameters: &get_home_status::Parameters) -> Result<HomeStatus> {
+        get_home_status::get_home_status(self, parameters)
+    }
+
+    fn get_station_data(&self, device_id: &str) -> Result<StationData> {
+        get_station_data::get_station_data(self, device_id)
+    }
+
+    fn get_homecoachs_data(&self, device_id: &str) -> Result<StationData> {
+        get_station_data::get_homecoachs_data(self, device_id)
+    }
+
+    fn get_last_event_of(&self, parameters: &get_last_event_of::Parameters) -> Result<EventsList> {
+        get_last_event_of::get_last_event_of(self, parameters)
+    }
+
+    fn get_measure(&self, parameters: &get_measure::Parameters) -> Result<Measure> {
+        get_measure::get_measure(self, parameters)
+    }
+
+    fn get_room_measure(&self, parameters: &get_room_measure::Parameters) -> Result<RoomMeasure> {
+        get_room_measure::get_room_measure(self, parameters)
+    }
+
+    fn get_next_events(&self, parameters: &get_next_events::Parameters) -> Result<EventsList> {
+        get_next_events::get_next_events(self, parameters)
+    }
+
+    fn get_public_data(&self, parameters: &get_public_data::Parameters) -> Result<PublicData> {
+        get_public_data::get_public_data(self, parameters)
+    }
+
+    fn set_persons_away(&self, parameters: &set_persons_away::Parameters) -> Result<set_persons_away::Response> {
+        set_persons_away::set_persons_away(self, parameters)
+    }
+
+    fn set_persons_home(&self, parameters: &set_persons_home::Parameters) -> Result<set_persons_home::Response> {
+        set_persons_home::set_persons_home(self, parameters)
+    }
+
+    fn set_room_thermpoint(
+        &self,
+        parameters: &set_room_thermpoint::Parameters,
+    ) -> Result<set_room_thermpoint::Response> {
+        set_room_thermpoint::set_room_thermpoint(self, parameters)
+    }
+
+    fn set_therm_mode(&self, parameters: &set_therm_mode::Parameters) -> Result<set_therm_mode::Response> {
+        set_therm_mode::set_therm_mode(self, parameters)
+    }
+
+    fn create_new_home_schedule(
+        &self,
+        parameters: &create_new_home_schedule::Parameters,
+    ) -> Result<create_new_home_schedule::Response> {
+        create_new_home_schedule::create_new_home_schedule(self, parameters)
+    }
+
+    fn delete_home_schedule(
+        &self,
+        parameters: &delete_home_schedule::Parameters,
+    ) -> Result<delete_home_schedule::Response> {
+        delete_home_schedule::delete_home_schedule(self, parameters)
+    }
+
+    fn rename_home_schedule(
+        &self,
+        parameters: &rename_home_schedule::Parameters,
+    ) -> Result<rename_home_schedule::Response> {
+        rename_home_schedule::rename_home_schedule(self, parameters)
+    }
+
+    fn switch_home_schedule(
+        &self,
+        parameters: &switch_home_schedule::Parameters,
+    ) -> Result<switch_home_schedule::Response> {
+        switch_home_schedule::switch_home_schedule(self, parameters)
+    }
+
+    fn sync_home_schedule(&self, parameters: &sync_home_schedule::Parameters) -> Result<sync_home_schedule::Response> {
+        sync_home_schedule::sync_home_schedule(self, parameters)
+    }
+
+    fn set_state(&self, parameters: &set_state::Parameters) -> Result<set_state::Response> {
+        set_state::set_state(self, parameters)
+    }
+}
@@ -0,0 +1,303 @@
+#[cfg(feature = "async")]
+use crate::client::{AsyncAuthenticatedClient, AsyncUnauthenticatedClient};
+#[cfg(feature = "blocking")]
+use crate::client::{AuthenticatedClient, UnauthenticatedClient};
+use crate::{
+    client::{
+        authenticate::Token, picture_cache::PictureCache, rate_limit::RateLimiter, retry::RetryPolicy,
+        ClientCredentials, Config,
+    },
+    errors::{ErrorKind, Result},
+};
+
+use failure::Fail;
+use reqwest::{Proxy, Url};
+use std::{sync::Arc, time::Duration};
+
+/// Configures the HTTP connection to Netatmo's API before creating a client
+///
+/// Start with `NetatmoClient::builder()`.
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    config: Config,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    proxy: Option<Proxy>,
+    #[cfg(feature = "blocking")]
+    http: Option<reqwest::blocking::Client>,
+    #[cfg(feature = "async")]
+    async_http: Option<reqwest::Client>,
+}
+
+/// Applies the settings shared by `reqwest`'s blocking and async client builders
+macro_rules! configure_http {
+    ($builder:expr, $settings:expr) => {{
+        let mut builder = $builder;
+        if let Some(timeout) = $settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = $settings.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(user_agent) = $settings.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(proxy) = $settings.proxy {
+            builder = builder.proxy(proxy);
+        }
+        builder.build().map_err(|e| e.context(ErrorKind::FailedToBuildClient))?
+    }};
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        ClientBuilder::default()
+    }
+
+    /// Sets the URL all endpoints are relative to; defaults to `https://api.netatmo.com`
+    ///
+    /// Building the client fails with `ErrorKind::InvalidBaseUrl` unless `base_url` is an absolute HTTP(S) URL.
+    pub fn base_url(self, base_url: &str) -> Self {
+        ClientBuilder {
+            config: Config {
+                base_url: base_url.trim_end_matches('/').to_string(),
+                ..self.config
+            },
+            ..self
+        }
+    }
+
+    /// Retries failed calls according to `retry_policy`; by default, calls fail on the first error
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        ClientBuilder {
+            config: Config {
+                retry_policy,
+                ..self.config
+            },
+            ..self
+        }
+    }
+
+    /// Keeps authenticated calls within the limits of `rate_limiter`; share it between clients of the same user
+    pub fn rate_limiter(self, rate_limiter: Arc<RateLimiter>) -> Self {
+        ClientBuilder {
+            config: Config {
+                rate_limiter: Some(rate_limiter),
+                ..self.config
+            },
+            ..self
+        }
+    }
+
+    /// Limits the raw response body kept in `ErrorKind::JsonDeserializationFailed` to `limit` bytes; `None` keeps
+    /// the whole body
+    pub fn error_body_limit(self, limit: Option<usize>) -> Self {
+        ClientBuilder {
+            config: Config {
+                error_body_limit: limit,
+                ..self.config
+            },
+            ..self
+        }
+    }
+
+    /// Skips malformed elements of the arrays named by `ApiResponse::LENIENT_ARRAYS`, e.g., devices, modules, rooms or
+    /// events, instead of failing the whole response
+    ///
+    /// Skipped elements are reported in the `warnings` of the responses implementing `ApiResponse::set_warnings`.
+    pub fn lenient(self, lenient: bool) -> Self {
+        ClientBuilder {
+            config: Config { lenient, ..self.config },
+            ..self
+        }
+    }
+
+    /// Fails calls with `ErrorKind::UnmappedFields` if a response contains fields the models do not cover
+    ///
+    /// Only responses implementing `ApiResponse` with `extra` maps keep such fields and are checked; other responses
+    /// like `set_room_thermpoint::Response` or `get_measure::Measure` ignore unknown fields even then.
+    pub fn strict(self, strict: bool) -> Self {
+        ClientBuilder {
+            config: Config { strict, ..self.config },
+            ..self
+        }
+    }
+
+    /// Serves camera pictures from `picture_cache` and stores downloaded ones there
+    pub fn picture_cache(self, picture_cache: PictureCache) -> Self {
+        ClientBuilder {
+            config: Config {
+                picture_cache: Some(picture_cache),
+                ..self.config
+            },
+            ..self
+        }
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        ClientBuilder {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn connect_timeout(self, connect_timeout: Duration) -> Self {
+        ClientBuilder {
+            connect_timeout: Some(connect_timeout),
+            ..self
+        }
+    }
+
+    pub fn user_agent(self, user_agent: &str) -> Self {
+        ClientBuilder {
+            user_agent: Some(user_agent.to_string()),
+            ..self
+        }
+    }
+
+    pub fn proxy(self, proxy: Proxy) -> Self {
+        ClientBuilder {
+            proxy: Some(proxy),
+            ..self
+        }
+    }
+
+    /// Uses a preconfigured HTTP client; timeouts, user agent and proxy of this builder are ignored then
+    #[cfg(feature = "blocking")]
+    pub fn http_client(self, http: reqwest::blocking::Client) -> Self {
+        ClientBuilder {
+            http: Some(http),
+            ..self
+        }
+    }
+
+    /// Uses a preconfigured async HTTP client; timeouts, user agent and proxy of this builder are ignored then
+    #[cfg(feature = "async")]
+    pub fn async_http_client(self, http: reqwest::Client) -> Self {
+        ClientBuilder {
+            async_http: Some(http),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn build<'a>(self, client_credentials: &'a ClientCredentials) -> Result<UnauthenticatedClient<'a>> {
+        let (http, config) = self.into_blocking_parts()?;
+        Ok(UnauthenticatedClient::new(client_credentials, http, config))
+    }
+
+    /// Builds a client from a previously obtained token; cf. `NetatmoClient::with_token`
+    #[cfg(feature = "blocking")]
+    pub fn build_with_token(self, token: Token) -> Result<AuthenticatedClient> {
+        let (http, config) = self.into_blocking_parts()?;
+        Ok(AuthenticatedClient::new(token, http, config))
+    }
+
+    #[cfg(feature = "async")]
+    pub fn build_async<'a>(self, client_credentials: &'a ClientCredentials) -> Result<AsyncUnauthenticatedClient<'a>> {
+        let (http, config) = self.into_async_parts()?;
+        Ok(AsyncUnauthenticatedClient::new(client_credentials, http, config))
+    }
+
+    /// Builds an async client from a previously obtained token; cf. `NetatmoClient::with_token`
+    #[cfg(feature = "async")]
+    pub fn build_async_with_token(self, token: Token) -> Result<AsyncAuthenticatedClient> {
+        let (http, config) = self.into_async_parts()?;
+        Ok(AsyncAuthenticatedClient::new(token, http, config))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn into_blocking_parts(self) -> Result<(reqwest::blocking::Client, Config)> {
+        check_base_url(&self.config.base_url)?;
+        if let Some(http) = self.http {
+            return Ok((http, self.config));
+        }
+        let http = configure_http!(reqwest::blocking::Client::builder(), self);
+
+        Ok((http, self.config))
+    }
+
+    #[cfg(feature = "async")]
+    fn into_async_parts(self) -> Result<(reqwest::Client, Config)> {
+        check_base_url(&self.config.base_url)?;
+        if let Some(http) = self.async_http {
+            return Ok((http, self.config));
+        }
+        let http = configure_http!(reqwest::Client::builder(), self);
+
+        Ok((http, self.config))
+    }
+}
+
+fn check_base_url(base_url: &str) -> Result<()> {
+    let invalid = || ErrorKind::InvalidBaseUrl {
+        url: base_url.to_string(),
+    };
+    let url = Url::parse(base_url).map_err(|e| e.context(invalid()))?;
+    match url.scheme() {
+        "http" | "https" if !url.cannot_be_a_base() => Ok(()),
+        _ => Err(invalid().into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod check_base_url {
+        use super::*;
+
+        #[test]
+        fn accept_http_urls() {
+            assert_that(&check_base_url("https://api.netatmo.com")).is_ok();
+            assert_that(&check_base_url("http://127.0.0.1:8080/netatmo")).is_ok();
+        }
+
+        #[test]
+        fn reject_relative_and_non_http_urls() {
+            for url in &[
+                "api.netatmo.com",
+                "",
+                "ftp://api.netatmo.com",
+                "mailto:user@example.com",
+            ] {
+                assert_that(&check_base_url(url).map_err(|e| e.kind().clone()))
+                    .is_err_containing(ErrorKind::InvalidBaseUrl { url: url.to_string() });
+            }
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod build {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{client, now, set_room_thermpoint, token, RESPONSE},
+        };
+
+        #[test]
+        fn fail_on_invalid_base_url() {
+            let res = ClientBuilder::new()
+                .base_url("api.netatmo.com")
+                .build_with_token(token(now()));
+
+            assert_that(&res.map(|_| ()).map_err(|e| e.kind().clone())).is_err_containing(ErrorKind::InvalidBaseUrl {
+                url: "api.netatmo.com".to_string(),
+            });
+        }
+
+        #[test]
+        fn use_base_url() {
+            let server = FakeServer::start(vec![(200, RESPONSE)]);
+            let client = client(&server, token(now()));
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            assert_that(&server.requests()[0].target).is_equal_to("/api/setroomthermpoint".to_string());
+        }
+    }
+}
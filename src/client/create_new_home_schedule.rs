@@ -0,0 +1,139 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{get_homes_data::ThermSchedule, response::ApiResponse},
+    errors::{Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    schedule: &'a ThermSchedule,
+}
+
+impl<'a> Parameters<'a> {
+    /// Creates a schedule from name, timetable, zones and temperatures of `schedule`; its id is ignored
+    pub fn new(home_id: &'a str, schedule: &'a ThermSchedule) -> Self {
+        Parameters { home_id, schedule }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.schedule.name.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::from(ErrorKind::InvalidParameters {
+            name: "create_new_home_schedule",
+            msg: "schedule has no name".to_string(),
+        }))
+    }
+}
+
+impl<'a> From<&'a Parameters<'a>> for Value {
+    fn from(p: &'a Parameters) -> Value {
+        json!({
+            "home_id": p.home_id,
+            "name": p.schedule.name,
+            "timetable": p.schedule.timetable,
+            "zones": p.schedule.zones,
+            "hg_temp": p.schedule.hg_temp,
+            "away_temp": p.schedule.away_temp,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+    pub body: Body,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Body {
+    pub schedule_id: String,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/energy#createnewhomeschedule
+#[cfg(feature = "blocking")]
+pub fn create_new_home_schedule(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client.call_write_json("create_new_home_schedule", "api/createnewhomeschedule", &body)
+}
+
+#[cfg(feature = "async")]
+pub async fn create_new_home_schedule_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client
+        .call_write_json("create_new_home_schedule", "api/createnewhomeschedule", &body)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn leave_out_schedule_id() {
+            let schedule = ThermSchedule {
+                id: "5a9e6c56".to_string(),
+                name: "Winter".to_string(),
+                ..ThermSchedule::default()
+            };
+            let parameters = Parameters::new("home", &schedule);
+
+            let body: Value = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&body["home_id"].as_str()).is_equal_to(Some("home"));
+            assert_that(&body["name"].as_str()).is_equal_to(Some("Winter"));
+            assert_that(&body.get("id")).is_none();
+            assert_that(&body.get("schedule_id")).is_none();
+        }
+
+        #[test]
+        fn reject_schedule_without_name() {
+            let schedule = ThermSchedule::default();
+
+            let res = Parameters::new("home", &schedule)
+                .validate()
+                .map_err(|e| e.kind().clone());
+
+            assert_that(&res).is_err_containing(ErrorKind::InvalidParameters {
+                name: "create_new_home_schedule",
+                msg: "schedule has no name".to_string(),
+            });
+        }
+    }
+
+    mod response {
+        use super::*;
+
+        #[test]
+        fn parse_schedule_id() {
+            let json = r#"{"status": "ok", "time_server": 1580000000, "body": {"schedule_id": "5e2f0042"}}"#;
+
+            let response: Response = serde_json::from_str(json).expect("Failed to parse response");
+
+            assert_that(&response.body.schedule_id).is_equal_to("5e2f0042".to_string());
+        }
+    }
+}
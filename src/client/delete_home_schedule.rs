@@ -0,0 +1,83 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{client::response::ApiResponse, errors::Result};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    schedule_id: &'a str,
+}
+
+impl<'a> Parameters<'a> {
+    /// Deletes `schedule_id`; Netatmo refuses to delete the active schedule
+    pub fn new(home_id: &'a str, schedule_id: &'a str) -> Self {
+        Parameters { home_id, schedule_id }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        map.insert("home_id", p.home_id.to_string());
+        map.insert("schedule_id", p.schedule_id.to_string());
+
+        map
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/energy#deletehomeschedule
+#[cfg(feature = "blocking")]
+pub fn delete_home_schedule(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("delete_home_schedule", "api/deletehomeschedule", &params)
+}
+
+#[cfg(feature = "async")]
+pub async fn delete_home_schedule_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client
+        .call_write("delete_home_schedule", "api/deletehomeschedule", &params)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn send_schedule_id() {
+            let parameters = Parameters::new("home", "5a9e6c56");
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params["home_id"]).is_equal_to("home".to_string());
+            assert_that(&params["schedule_id"]).is_equal_to("5a9e6c56".to_string());
+            assert_that(&params).has_length(2);
+        }
+    }
+}
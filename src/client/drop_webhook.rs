@@ -0,0 +1,94 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{add_webhook::AppType, response::ApiResponse},
+    errors::Result,
+};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct Parameters {
+    app_type: Option<AppType>,
+}
+
+impl Parameters {
+    /// Drops the webhook registered by this application
+    pub fn new() -> Self {
+        Parameters::default()
+    }
+
+    pub fn app_type(self, app_type: AppType) -> Self {
+        Parameters {
+            app_type: Some(app_type),
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        if let Some(ref app_type) = p.app_type {
+            map.insert("app_types", app_type.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: Option<usize>,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#dropwebhook
+#[cfg(feature = "blocking")]
+pub(crate) fn drop_webhook(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("drop_webhook", "api/dropwebhook", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn drop_webhook_async(client: &AsyncAuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("drop_webhook", "api/dropwebhook", &params).await
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::{
+        local_http::FakeServer,
+        test_support::{client, form, now, token, RESPONSE},
+        Netatmo,
+    };
+
+    #[test]
+    fn send_app_type() {
+        let server = FakeServer::start(vec![(200, RESPONSE)]);
+        let client = client(&server, token(now()));
+        let parameters = Parameters::new().app_type(AppType::Security);
+
+        let res = client.drop_webhook(&parameters);
+
+        assert_that(&res).is_ok();
+        let requests = server.requests();
+        let form = form(&requests[0]);
+        assert_that(&requests[0].target).is_equal_to("/api/dropwebhook".to_string());
+        assert_that(&form["app_types"]).is_equal_to("app_security".to_string());
+        assert_that(&form.contains_key("url")).is_false();
+    }
+}
@@ -0,0 +1,245 @@
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+#[cfg(feature = "async")]
+use crate::client::{asynchronous::run_blocking, AsyncAuthenticatedClient};
+use crate::{
+    client::{api_call_failure, picture_cache::PictureCache, Config, FromHttpResponse},
+    errors::{ErrorKind, Result},
+};
+
+use failure::Fail;
+use log::warn;
+use reqwest::StatusCode;
+use std::{collections::HashMap, fs, path::Path};
+
+/// Image referenced by `id` and `key` of a snapshot, vignette or face; cf. `Image::picture_parameters`
+pub struct Parameters<'a> {
+    image_id: &'a str,
+    key: &'a str,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(image_id: &'a str, key: &'a str) -> Self {
+        Parameters { image_id, key }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("image_id", p.image_id.to_string());
+        m.insert("key", p.key.to_string());
+
+        m
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Picture {
+    /// Detected from the image data, e.g., `image/jpeg`; falls back to the `Content-Type` header
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl Picture {
+    /// Writes the picture to `path` and returns its content type
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<&str> {
+        let path = path.as_ref();
+        fs::write(path, &self.bytes).map_err(|e| {
+            e.context(ErrorKind::FailedToWriteFile {
+                path: path.display().to_string(),
+            })
+        })?;
+
+        Ok(&self.content_type)
+    }
+}
+
+impl FromHttpResponse for Picture {
+    fn from_http_response(
+        _config: &Config,
+        name: &'static str,
+        status: StatusCode,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Self> {
+        if status != StatusCode::OK {
+            return Err(api_call_failure(name, status, &String::from_utf8_lossy(body)));
+        }
+
+        Ok(Picture {
+            content_type: detect_content_type(body, content_type),
+            bytes: body.to_vec(),
+        })
+    }
+}
+
+/// Checks the leading bytes of `bytes` for the image formats used by Netatmo's cameras
+fn detect_content_type(bytes: &[u8], header: Option<&str>) -> String {
+    let detected = if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    };
+
+    detected
+        .or_else(|| header.and_then(|x| x.split(';').next()).map(str::trim))
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+fn load_cached(cache: Option<&PictureCache>, parameters: &Parameters) -> Option<Picture> {
+    match cache?.load(parameters.image_id, parameters.key) {
+        Ok(picture) => picture,
+        Err(e) => {
+            warn!("Failed to load picture from cache: {}", e);
+            None
+        }
+    }
+}
+
+fn store_cached(cache: Option<&PictureCache>, parameters: &Parameters, picture: &Picture) {
+    if let Some(cache) = cache {
+        // The picture has been downloaded regardless, so a failing cache must not fail the call.
+        if let Err(e) = cache.store(parameters.image_id, parameters.key, picture) {
+            warn!("Failed to store picture in cache: {}", e);
+        }
+    }
+}
+
+/// Runs `load_cached` on tokio's blocking thread pool
+#[cfg(feature = "async")]
+async fn load_cached_async(cache: Option<&PictureCache>, parameters: &Parameters<'_>) -> Option<Picture> {
+    let cache = cache?.clone();
+    let (image_id, key) = (parameters.image_id.to_string(), parameters.key.to_string());
+    run_blocking(ErrorKind::PictureCacheFailed, move || {
+        load_cached(Some(&cache), &Parameters::new(&image_id, &key))
+    })
+    .await
+    .unwrap_or_else(|e| {
+        warn!("Failed to load picture from cache: {}", e);
+        None
+    })
+}
+
+/// Runs `store_cached` on tokio's blocking thread pool
+#[cfg(feature = "async")]
+async fn store_cached_async(cache: Option<&PictureCache>, parameters: &Parameters<'_>, picture: &Picture) {
+    let cache = match cache {
+        Some(cache) => cache.clone(),
+        None => return,
+    };
+    let (image_id, key, picture) = (
+        parameters.image_id.to_string(),
+        parameters.key.to_string(),
+        picture.clone(),
+    );
+    let stored = run_blocking(ErrorKind::PictureCacheFailed, move || {
+        store_cached(Some(&cache), &Parameters::new(&image_id, &key), &picture)
+    })
+    .await;
+    if let Err(e) = stored {
+        warn!("Failed to store picture in cache: {}", e);
+    }
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#getcamerapicture
+#[cfg(feature = "blocking")]
+pub(crate) fn get_camera_picture(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Picture> {
+    if let Some(picture) = load_cached(client.picture_cache(), parameters) {
+        return Ok(picture);
+    }
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    let picture = client.call("get_camera_picture", "api/getcamerapicture", &params)?;
+    store_cached(client.picture_cache(), parameters, &picture);
+
+    Ok(picture)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_camera_picture_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Picture> {
+    if let Some(picture) = load_cached_async(client.picture_cache(), parameters).await {
+        return Ok(picture);
+    }
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    let picture = client
+        .call("get_camera_picture", "api/getcamerapicture", &params)
+        .await?;
+    store_cached_async(client.picture_cache(), parameters, &picture).await;
+
+    Ok(picture)
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod detect_content_type {
+        use super::*;
+
+        #[test]
+        fn detect_from_image_data() {
+            let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+
+            assert_that(&detect_content_type(&jpeg, Some("application/octet-stream")))
+                .is_equal_to("image/jpeg".to_string());
+            assert_that(&detect_content_type(b"GIF89a...", None)).is_equal_to("image/gif".to_string());
+        }
+
+        #[test]
+        fn fall_back_to_header() {
+            assert_that(&detect_content_type(b"...", Some("image/bmp; charset=binary")))
+                .is_equal_to("image/bmp".to_string());
+            assert_that(&detect_content_type(b"...", None)).is_equal_to("application/octet-stream".to_string());
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod authenticated_client {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{form, now, token},
+            Netatmo, NetatmoClient,
+        };
+
+        #[test]
+        fn serve_downloaded_picture_from_cache() {
+            let server = FakeServer::start(vec![(200, "GIF89a")]);
+            let dir = std::env::temp_dir().join(format!("netatmo-rs-test-picture-cache-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            let client = NetatmoClient::builder()
+                .base_url(server.url())
+                .picture_cache(PictureCache::new(&dir))
+                .build_with_token(token(now()))
+                .expect("Failed to build client");
+            let parameters = Parameters::new("5e2f0003", "g7h8i9");
+
+            let downloaded = client.get_camera_picture(&parameters);
+            let cached = client.get_camera_picture(&parameters);
+
+            let downloaded = downloaded.expect("Failed to download picture");
+            let cached = cached.expect("Failed to load cached picture");
+            assert_that(&downloaded.content_type).is_equal_to("image/gif".to_string());
+            assert_that(&cached.content_type).is_equal_to(&downloaded.content_type);
+            assert_that(&cached.bytes).is_equal_to(b"GIF89a".to_vec());
+            let requests = server.requests();
+            assert_that(&requests).has_length(1);
+            assert_that(&form(&requests[0])["image_id"]).is_equal_to("5e2f0003".to_string());
+        }
+    }
+}
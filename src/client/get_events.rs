@@ -0,0 +1,283 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{
+        get_home_data::Event,
+        response::{self, unmapped, ApiResponse, Warning},
+    },
+    errors::Result,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    device_id: Option<&'a str>,
+    module_id: Option<&'a str>,
+    event_id: Option<&'a str>,
+    person_id: Option<&'a str>,
+    offset: Option<usize>,
+    size: Option<usize>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(home_id: &'a str) -> Self {
+        Parameters {
+            home_id,
+            device_id: None,
+            module_id: None,
+            event_id: None,
+            person_id: None,
+            offset: None,
+            size: None,
+        }
+    }
+
+    pub fn device_id(self, device_id: &'a str) -> Self {
+        Parameters {
+            device_id: Some(device_id),
+            ..self
+        }
+    }
+
+    pub fn module_id(self, module_id: &'a str) -> Self {
+        Parameters {
+            module_id: Some(module_id),
+            ..self
+        }
+    }
+
+    /// Returns the events older than this one
+    pub fn event_id(self, event_id: &'a str) -> Self {
+        Parameters {
+            event_id: Some(event_id),
+            ..self
+        }
+    }
+
+    pub fn person_id(self, person_id: &'a str) -> Self {
+        Parameters {
+            person_id: Some(person_id),
+            ..self
+        }
+    }
+
+    pub fn offset(self, offset: usize) -> Self {
+        Parameters {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    pub fn size(self, size: usize) -> Self {
+        Parameters {
+            size: Some(size),
+            ..self
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("home_id", p.home_id.to_string());
+        if let Some(device_id) = p.device_id {
+            m.insert("device_id", device_id.to_string());
+        }
+        if let Some(module_id) = p.module_id {
+            m.insert("module_id", module_id.to_string());
+        }
+        if let Some(event_id) = p.event_id {
+            m.insert("event_id", event_id.to_string());
+        }
+        if let Some(person_id) = p.person_id {
+            m.insert("person_id", person_id.to_string());
+        }
+        if let Some(offset) = p.offset {
+            m.insert("offset", offset.to_string());
+        }
+        if let Some(size) = p.size {
+            m.insert("size", size.to_string());
+        }
+
+        m
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Events {
+    pub body: Body,
+    pub status: String,
+    pub time_exec: Option<f64>,
+    pub time_server: i64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+}
+
+impl ApiResponse for Events {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["events"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Body {
+    pub home: Home,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Home {
+    pub id: String,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Response of `getnextevents`, `geteventsuntil` and `getlasteventof`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventsList {
+    pub body: EventsListBody,
+    pub status: String,
+    pub time_exec: Option<f64>,
+    pub time_server: i64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+}
+
+impl ApiResponse for EventsList {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["events_list"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventsListBody {
+    /// Events ordered from newest to oldest
+    #[serde(default)]
+    pub events_list: Vec<Event>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+unmapped! {
+    Events { body };
+    Body { home };
+    Home { events };
+    EventsList { body };
+    EventsListBody { events_list };
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#getevents
+#[cfg(feature = "blocking")]
+pub(crate) fn get_events(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Events> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_events", "api/getevents", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_events_async(client: &AsyncAuthenticatedClient, parameters: &Parameters<'_>) -> Result<Events> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_events", "api/getevents", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::get_home_data::EventType;
+
+    mod get_events {
+        use super::*;
+
+        #[test]
+        fn parse_response() {
+            let json = r#"{
+  "body": {
+    "home": {
+      "id": "5a1b2c3d4e5f6a7b8c9d0e1f",
+      "events": [
+        {
+          "id": "5e2f0010",
+          "type": "outdoor",
+          "time": 1580000300,
+          "module_id": "70:ee:50:00:00:02",
+          "video_id": "5e2f0011",
+          "video_status": "available",
+          "subevents": [
+            {
+              "id": "5e2f0012",
+              "type": "vehicle",
+              "time": 1580000301,
+              "verified": true,
+              "offset": 1,
+              "snapshot": {"url": "https://netatmocameraimage.blob.core.windows.net/snapshot"},
+              "vignette": {"url": "https://netatmocameraimage.blob.core.windows.net/vignette"},
+              "message": "Vehicle detected"
+            }
+          ]
+        },
+        {
+          "id": "5e2f0013",
+          "type": "sd",
+          "time": 1580000200,
+          "module_id": "70:ee:50:00:00:01",
+          "sub_type": 4,
+          "message": "SD card is working"
+        },
+        {
+          "id": "5e2f0014",
+          "type": "connection",
+          "time": 1580000100,
+          "module_id": "70:ee:50:00:00:01",
+          "message": "Hall connected"
+        }
+      ]
+    }
+  },
+  "status": "ok",
+  "time_server": 1580000400
+}"#;
+
+            let events: Events = serde_json::from_str(json).expect("Failed to parse events");
+
+            let events = &events.body.home.events;
+            assert_that(&events.iter().map(Event::event_type).collect::<Vec<_>>()).is_equal_to(vec![
+                EventType::Outdoor,
+                EventType::Sd,
+                EventType::Connection,
+            ]);
+            assert_that(&events[0].sub_events()[0].event_type()).is_equal_to(EventType::Vehicle);
+            assert_that(&events[0].sub_events()[0].snapshot.as_ref().and_then(|x| x.url.clone())).is_some();
+            assert_that(&events[1].sub_type).is_equal_to(Some(4));
+        }
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{client::get_events::EventsList, errors::Result};
+
+use std::collections::HashMap;
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    event_id: &'a str,
+}
+
+impl<'a> Parameters<'a> {
+    /// Returns all events newer than `event_id`, e.g., the newest event already seen
+    pub fn new(home_id: &'a str, event_id: &'a str) -> Self {
+        Parameters { home_id, event_id }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("home_id", p.home_id.to_string());
+        m.insert("event_id", p.event_id.to_string());
+
+        m
+    }
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#geteventsuntil
+#[cfg(feature = "blocking")]
+pub(crate) fn get_events_until(client: &AuthenticatedClient, parameters: &Parameters) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_events_until", "api/geteventsuntil", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_events_until_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_events_until", "api/geteventsuntil", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod get_events_until {
+        use super::*;
+
+        #[test]
+        fn parameters() {
+            let parameters = Parameters::new("5e2f0001", "5e2f0042");
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params["home_id"]).is_equal_to("5e2f0001".to_string());
+            assert_that(&params["event_id"]).is_equal_to("5e2f0042".to_string());
+            assert_that(&params).has_length(2);
+        }
+    }
+}
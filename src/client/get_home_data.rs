@@ -0,0 +1,464 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{
+        get_camera_picture,
+        response::{self, unmapped, ApiResponse, Warning},
+    },
+    errors::Result,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HomeData {
+    pub body: Body,
+    pub status: String,
+    pub time_exec: f64,
+    pub time_server: i64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+}
+
+impl ApiResponse for HomeData {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["homes", "cameras", "persons", "events"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Body {
+    pub homes: Vec<Home>,
+    pub user: User,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Home {
+    pub id: String,
+    pub name: String,
+    pub place: Option<Place>,
+    #[serde(default)]
+    pub cameras: Vec<Camera>,
+    #[serde(default)]
+    pub persons: Vec<Person>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Home {
+    /// Returns the id of person `id` if this home knows that person
+    pub fn person_id(&self, id: &str) -> Option<PersonId> {
+        self.persons.iter().find(|x| x.id == id).map(|x| PersonId {
+            home_id: self.id.clone(),
+            id: x.id.clone(),
+        })
+    }
+
+    pub fn person_ids(&self) -> Vec<PersonId> {
+        self.persons
+            .iter()
+            .map(|x| PersonId {
+                home_id: self.id.clone(),
+                id: x.id.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Id of a person that has been checked against the persons of a home; cf. `Home::person_id`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PersonId {
+    home_id: String,
+    id: String,
+}
+
+impl PersonId {
+    pub fn home_id(&self) -> &str {
+        &self.home_id
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+}
+
+impl fmt::Display for PersonId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub timezone: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    pub id: String,
+    /// `NACamera` for Welcome, `NOC` for Presence
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub name: String,
+    /// `on`, `off` or `disconnected`
+    pub status: String,
+    pub sd_status: Option<String>,
+    pub alim_status: Option<String>,
+    /// Base URL for live streams and pictures; changes whenever the camera reconnects
+    pub vpn_url: Option<String>,
+    /// Whether the camera is in the same local network as the caller of the API
+    pub is_local: Option<bool>,
+    pub light_mode_status: Option<String>,
+    pub use_pin_code: Option<bool>,
+    pub last_setup: Option<i64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Person {
+    pub id: String,
+    /// Name of a known person; unknown faces have none
+    pub pseudo: Option<String>,
+    pub last_seen: Option<i64>,
+    #[serde(default)]
+    pub out_of_sight: bool,
+    pub face: Option<Image>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Person {
+    pub fn is_known(&self) -> bool {
+        self.pseudo.is_some()
+    }
+}
+
+/// Reference to a snapshot, vignette or face; cf. `get_camera_picture`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    pub id: Option<String>,
+    pub version: Option<i64>,
+    pub key: Option<String>,
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Image {
+    /// Returns the parameters to download this image unless it lacks `id` or `key`
+    pub fn picture_parameters(&self) -> Option<get_camera_picture::Parameters<'_>> {
+        Some(get_camera_picture::Parameters::new(
+            self.id.as_deref()?,
+            self.key.as_deref()?,
+        ))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    /// E.g., `person`, `movement`, `outdoor`, `connection` or `sd`
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub time: i64,
+    /// Set by `gethomedata` and the legacy event endpoints
+    pub camera_id: Option<String>,
+    pub device_id: Option<String>,
+    /// Set by `getevents` instead of `camera_id`
+    pub module_id: Option<String>,
+    pub person_id: Option<String>,
+    pub message: Option<String>,
+    pub snapshot: Option<Image>,
+    pub vignette: Option<Image>,
+    pub video_id: Option<String>,
+    pub video_status: Option<String>,
+    pub is_arrival: Option<bool>,
+    pub sub_type: Option<i64>,
+    /// Detections of a Presence `outdoor` event
+    #[serde(default)]
+    pub event_list: Vec<SubEvent>,
+    /// Detections of a Presence `outdoor` event as reported by `getevents`
+    #[serde(default)]
+    pub subevents: Vec<SubEvent>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Event {
+    pub fn event_type(&self) -> EventType {
+        EventType::from(self.type_field.as_str())
+    }
+
+    /// Returns the detections of an `outdoor` event regardless of the endpoint it came from
+    pub fn sub_events(&self) -> &[SubEvent] {
+        if self.subevents.is_empty() {
+            &self.event_list
+        } else {
+            &self.subevents
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubEvent {
+    pub id: String,
+    /// E.g., `human`, `animal` or `vehicle`
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub time: i64,
+    pub verified: Option<bool>,
+    pub offset: Option<i64>,
+    pub message: Option<String>,
+    pub snapshot: Option<Image>,
+    pub vignette: Option<Image>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl SubEvent {
+    pub fn event_type(&self) -> EventType {
+        EventType::from(self.type_field.as_str())
+    }
+}
+
+/// Type of an event or sub-event; cf. https://dev.netatmo.com/apidocumentation/security#events
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventType {
+    /// A known or unknown face has been seen
+    Person,
+    /// A known person left
+    PersonAway,
+    Movement,
+    /// Presence detected a human, an animal or a vehicle; cf. `Event::sub_events`
+    Outdoor,
+    Human,
+    Animal,
+    Vehicle,
+    Connection,
+    Disconnection,
+    On,
+    Off,
+    Boot,
+    /// SD card status changed; `sub_type` is 1 for missing, 2 for inserted, 3 for formatted, 4 for working, 5 for
+    /// defective, 6 for incompatible and 7 for too small
+    Sd,
+    /// Power supply status changed; `sub_type` is 1 for wrong and 2 for correct power supply
+    Alim,
+    Other(String),
+}
+
+impl From<&str> for EventType {
+    fn from(s: &str) -> Self {
+        match s {
+            "person" => EventType::Person,
+            "person_away" => EventType::PersonAway,
+            "movement" => EventType::Movement,
+            "outdoor" => EventType::Outdoor,
+            "human" => EventType::Human,
+            "animal" => EventType::Animal,
+            "vehicle" => EventType::Vehicle,
+            "connection" => EventType::Connection,
+            "disconnection" => EventType::Disconnection,
+            "on" => EventType::On,
+            "off" => EventType::Off,
+            "boot" => EventType::Boot,
+            "sd" => EventType::Sd,
+            "alim" => EventType::Alim,
+            other => EventType::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    pub reg_locale: Option<String>,
+    pub lang: Option<String>,
+    pub country: Option<String>,
+    pub mail: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+unmapped! {
+    HomeData { body };
+    Body { homes, user };
+    Home { place, cameras, persons, events };
+    Place {};
+    Camera {};
+    Person { face };
+    Image {};
+    Event { snapshot, vignette, event_list, subevents };
+    SubEvent { snapshot, vignette };
+    User {};
+}
+
+#[derive(Default)]
+pub struct Parameters<'a> {
+    home_id: Option<&'a str>,
+    size: Option<usize>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new() -> Self {
+        Parameters::default()
+    }
+
+    pub fn home_id(self, home_id: &'a str) -> Self {
+        Parameters {
+            home_id: Some(home_id),
+            ..self
+        }
+    }
+
+    /// Number of events to return per home
+    pub fn size(self, size: usize) -> Self {
+        Parameters {
+            size: Some(size),
+            ..self
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        if let Some(home_id) = p.home_id {
+            map.insert("home_id", home_id.to_string());
+        }
+        if let Some(size) = p.size {
+            map.insert("size", size.to_string());
+        }
+
+        map
+    }
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#gethomedata
+#[cfg(feature = "blocking")]
+pub(crate) fn get_home_data(client: &AuthenticatedClient, parameters: &Parameters) -> Result<HomeData> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_home_data", "api/gethomedata", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_home_data_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<HomeData> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_home_data", "api/gethomedata", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod get_home_data {
+        use super::*;
+
+        #[test]
+        fn parse_response() {
+            let json = r#"{
+  "body": {
+    "homes": [
+      {
+        "id": "5a1b2c3d4e5f6a7b8c9d0e1f",
+        "name": "Home",
+        "place": {"city": "Berlin", "country": "DE", "timezone": "Europe/Berlin"},
+        "cameras": [
+          {
+            "id": "70:ee:50:00:00:01",
+            "type": "NACamera",
+            "status": "on",
+            "vpn_url": "https://prodvpn-eu-2.netatmo.net/restricted/10.255.0.1/abcdef/MTU4MDAwMDAwMDo",
+            "is_local": true,
+            "sd_status": "on",
+            "alim_status": "on",
+            "name": "Hall",
+            "use_pin_code": false,
+            "last_setup": 1570000000
+          }
+        ],
+        "smokedetectors": [],
+        "persons": [
+          {
+            "id": "91827364-5555-4444-3333-222211110000",
+            "last_seen": 1580000000,
+            "out_of_sight": false,
+            "face": {"id": "5e2f0000", "version": 1, "key": "a1b2c3", "url": "https://netatmocameraimage.blob.core.windows.net/face"},
+            "pseudo": "Jane"
+          },
+          {
+            "id": "91827364-5555-4444-3333-222211110001",
+            "last_seen": 1579990000,
+            "out_of_sight": true,
+            "face": {"id": "5e2f0001", "version": 1, "key": "d4e5f6"}
+          }
+        ],
+        "events": [
+          {
+            "id": "5e2f0002",
+            "type": "person",
+            "time": 1580000000,
+            "camera_id": "70:ee:50:00:00:01",
+            "device_id": "70:ee:50:00:00:01",
+            "person_id": "91827364-5555-4444-3333-222211110000",
+            "snapshot": {"id": "5e2f0003", "version": 1, "key": "g7h8i9"},
+            "video_id": "5e2f0004",
+            "video_status": "available",
+            "is_arrival": true,
+            "message": "<b>Jane</b> seen"
+          }
+        ]
+      }
+    ],
+    "user": {"reg_locale": "de-DE", "lang": "de-DE", "country": "DE", "mail": "jane@example.com"},
+    "global_info": {"show_tags": true}
+  },
+  "status": "ok",
+  "time_exec": 0.05,
+  "time_server": 1580000000
+}"#;
+
+            let home_data: HomeData = serde_json::from_str(json).expect("Failed to parse home data");
+
+            let home = &home_data.body.homes[0];
+            assert_that(&home.cameras[0].is_local).is_equal_to(Some(true));
+            assert_that(&home.persons.iter().filter(|x| x.is_known()).count()).is_equal_to(1);
+            assert_that(&home.persons[1].out_of_sight).is_true();
+            assert_that(&home.events[0].snapshot.as_ref().and_then(|x| x.key.clone()))
+                .is_equal_to(Some("g7h8i9".to_string()));
+            assert_that(&home_data.unmapped_fields()).is_equal_to(vec![
+                "body.global_info".to_string(),
+                "body.homes[0].smokedetectors".to_string(),
+            ]);
+        }
+    }
+}
@@ -1,6 +1,14 @@
-use crate::{client::AuthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::response::{self, unmapped, ApiResponse, Warning},
+    errors::Result,
+};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
 pub use crate::get_homes_data::GatewayType;
@@ -10,11 +18,30 @@ pub struct HomeStatus {
     pub status: String,
     pub time_server: i64,
     pub body: Body,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ApiResponse for HomeStatus {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["modules", "rooms"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Body {
     pub home: Home,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -22,6 +49,8 @@ pub struct Home {
     pub id: String,
     pub modules: Vec<Module>,
     pub rooms: Vec<Room>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -52,6 +81,8 @@ pub struct Module {
     pub last_smoke_detected_end_time: Option<i64>,
     pub last_seen: Option<i64>,
     pub last_wifi_connection: Option<i64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -66,6 +97,16 @@ pub struct Room {
     pub therm_setpoint_end_time: i64,
     pub anticipating: bool,
     pub open_window: bool,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+unmapped! {
+    HomeStatus { body };
+    Body { home };
+    Home { modules, rooms };
+    Module {};
+    Room {};
 }
 
 #[derive(Default)]
@@ -115,8 +156,96 @@ impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
     }
 }
 
+#[cfg(feature = "blocking")]
 pub(crate) fn get_home_status(client: &AuthenticatedClient, parameters: &Parameters) -> Result<HomeStatus> {
     let params: HashMap<&str, String> = parameters.into();
-    let mut params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
-    client.call("get_home_status", "https://api.netatmo.com/api/homestatus", &mut params)
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_home_status", "api/homestatus", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_home_status_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<HomeStatus> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_home_status", "api/homestatus", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod handle_response {
+        use super::*;
+        use crate::{
+            client::{handle_response, Config},
+            errors::ErrorKind,
+        };
+        use reqwest::StatusCode;
+
+        const HOME_STATUS: &str = r#"{
+  "status": "ok",
+  "time_server": 1580000000,
+  "body": {
+    "home": {
+      "id": "5a1b2c3d4e5f6a7b8c9d0e20",
+      "modules": [
+        {"id": "70:ee:50:00:00:10", "type": "NATherm1", "firmware_revision": 65, "rf_strength": 60, "reachable": true, "battery_level": 4100, "boiler_status": false, "anticipating": false, "bridge": "70:ee:50:00:00:20", "battery_state": "full"}
+      ],
+      "rooms": [
+        {"id": "2255", "reachable": true, "therm_measured_temperature": 19.8, "heating_power_request": 0, "therm_setpoint_temperature": 19.5, "therm_setpoint_mode": "schedule", "therm_setpoint_start_time": 1579996800, "therm_setpoint_end_time": 0, "anticipating": false, "open_window": false},
+        {"id": "3366", "reachable": false, "heating_power_request": 0, "therm_setpoint_temperature": 12, "therm_setpoint_mode": "away", "therm_setpoint_start_time": 1579996800, "therm_setpoint_end_time": 0, "anticipating": false, "open_window": false}
+      ]
+    }
+  }
+}"#;
+
+        #[test]
+        fn skip_room_without_measured_temperature_in_lenient_mode() {
+            let config = Config {
+                lenient: true,
+                ..Config::default()
+            };
+
+            let res: Result<HomeStatus> = handle_response(&config, "get_home_status", StatusCode::OK, HOME_STATUS);
+
+            let home_status = res.expect("Failed to parse home status");
+            assert_that(&home_status.body.home.rooms).has_length(1);
+            assert_that(&home_status.body.home.rooms[0].id).is_equal_to("2255".to_string());
+            assert_that(&home_status.warnings).has_length(1);
+            assert_that(&home_status.warnings[0].skipped).is_equal_to("body.home.rooms[1]".to_string());
+            assert_that(&home_status.warnings[0].msg).contains("therm_measured_temperature");
+        }
+
+        #[test]
+        fn report_unmapped_fields_in_strict_mode() {
+            let mut json: Value = serde_json::from_str(HOME_STATUS).expect("Failed to parse fixture");
+            json["body"]["home"]["rooms"][1]["therm_measured_temperature"] = Value::from(17.2);
+            json["body"]["home"]["rooms"][1]["humidity"] = Value::from(45);
+            let config = Config {
+                strict: true,
+                ..Config::default()
+            };
+
+            let res: Result<HomeStatus> =
+                handle_response(&config, "get_home_status", StatusCode::OK, &json.to_string());
+
+            assert_that(&res.map_err(|e| e.kind().clone())).is_err_containing(ErrorKind::UnmappedFields {
+                name: "get_home_status",
+                fields: vec!["body.home.rooms[1].humidity".to_string()],
+            });
+        }
+
+        #[test]
+        fn fail_on_room_without_measured_temperature() {
+            let res: Result<HomeStatus> =
+                handle_response(&Config::default(), "get_home_status", StatusCode::OK, HOME_STATUS);
+
+            assert_that(&res).is_err();
+        }
+    }
 }
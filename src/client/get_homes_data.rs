@@ -1,6 +1,14 @@
-use crate::{client::AuthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::response::{self, unmapped, ApiResponse, Warning},
+    errors::Result,
+};
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{collections::HashMap, fmt};
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -9,12 +17,31 @@ pub struct HomesData {
     pub status: String,
     pub time_exec: f64,
     pub time_server: i64,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ApiResponse for HomesData {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["homes", "rooms", "modules"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Body {
     pub homes: Vec<Home>,
     pub user: User,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -28,6 +55,8 @@ pub struct Home {
     pub therm_setpoint_default_duration: i64,
     pub therm_mode: String,
     pub schedules: Vec<Schedule>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -37,6 +66,8 @@ pub struct Room {
     #[serde(rename = "type")]
     pub type_field: String,
     pub module_ids: Vec<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -49,6 +80,8 @@ pub struct Module {
     pub modules_bridged: Option<Vec<String>>,
     pub room_id: Option<String>,
     pub bridge: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -63,12 +96,16 @@ pub struct ThermSchedule {
     pub selected: bool,
     #[serde(rename = "type")]
     pub type_field: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Timetable {
     pub zone_id: i64,
     pub m_offset: i64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -78,13 +115,19 @@ pub struct Zone {
     #[serde(rename = "type")]
     pub type_field: i64,
     pub rooms_temp: Vec<RoomsTemp>,
+    // Absent rather than `null` when syncing a schedule back
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rooms: Option<Vec<RoomTemp>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RoomsTemp {
     pub room_id: String,
     pub temp: f64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -99,12 +142,16 @@ pub struct Schedule {
     pub selected: bool,
     #[serde(rename = "type")]
     pub type_field: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RoomTemp {
     pub id: String,
     pub therm_setpoint_temperature: f64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -117,6 +164,23 @@ pub struct User {
     pub unit_system: i64,
     pub unit_wind: i64,
     pub id: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+unmapped! {
+    HomesData { body };
+    Body { homes, user };
+    Home { rooms, modules, therm_schedules, schedules };
+    Room {};
+    Module {};
+    ThermSchedule { timetable, zones };
+    Timetable {};
+    Zone { rooms_temp, rooms };
+    RoomsTemp {};
+    Schedule { timetable, zones };
+    RoomTemp {};
+    User {};
 }
 
 #[derive(Default)]
@@ -183,8 +247,19 @@ impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
     }
 }
 
+#[cfg(feature = "blocking")]
 pub(crate) fn get_homes_data(client: &AuthenticatedClient, parameters: &Parameters) -> Result<HomesData> {
     let params: HashMap<&str, String> = parameters.into();
-    let mut params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
-    client.call("get_homes_data", "https://api.netatmo.com/api/homesdata", &mut params)
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_homes_data", "api/homesdata", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_homes_data_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<HomesData> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_homes_data", "api/homesdata", &params).await
 }
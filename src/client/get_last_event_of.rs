@@ -0,0 +1,94 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{client::get_events::EventsList, errors::Result};
+
+use std::collections::HashMap;
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    person_id: &'a str,
+    offset: Option<usize>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(home_id: &'a str, person_id: &'a str) -> Self {
+        Parameters {
+            home_id,
+            person_id,
+            offset: None,
+        }
+    }
+
+    /// Number of events to return in addition to the last event of the person
+    pub fn offset(self, offset: usize) -> Self {
+        Parameters {
+            offset: Some(offset),
+            ..self
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("home_id", p.home_id.to_string());
+        m.insert("person_id", p.person_id.to_string());
+        if let Some(offset) = p.offset {
+            m.insert("offset", offset.to_string());
+        }
+
+        m
+    }
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#getlasteventof
+#[cfg(feature = "blocking")]
+pub(crate) fn get_last_event_of(client: &AuthenticatedClient, parameters: &Parameters) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_last_event_of", "api/getlasteventof", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_last_event_of_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_last_event_of", "api/getlasteventof", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod get_last_event_of {
+        use super::*;
+
+        #[test]
+        fn parameters() {
+            let parameters = Parameters::new("5e2f0001", "a1b2c3d4").offset(5);
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params["home_id"]).is_equal_to("5e2f0001".to_string());
+            assert_that(&params["person_id"]).is_equal_to("a1b2c3d4".to_string());
+            assert_that(&params["offset"]).is_equal_to("5".to_string());
+        }
+
+        #[test]
+        fn omit_offset_by_default() {
+            let parameters = Parameters::new("5e2f0001", "a1b2c3d4");
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params.contains_key("offset")).is_false();
+        }
+    }
+}
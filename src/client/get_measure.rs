@@ -1,4 +1,8 @@
-use crate::{client::AuthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{client::response::ApiResponse, errors::Result};
 
 use serde::{Deserialize, Deserializer, Serialize};
 use std::{collections::HashMap, fmt, str::FromStr};
@@ -153,15 +157,28 @@ pub struct Measure {
     values: HashMap<usize, Vec<Option<f64>>>,
 }
 
+impl ApiResponse for Measure {}
+
 // cf. https://dev.netatmo.com/resources/technical/reference/common/getmeasure
+#[cfg(feature = "blocking")]
 pub fn get_measure(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Measure> {
     let params: HashMap<&str, String> = parameters.into();
-    let mut params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call("get_measure", "api/getmeasure", &params)
+}
+
+#[cfg(feature = "async")]
+pub async fn get_measure_async(client: &AsyncAuthenticatedClient, parameters: &Parameters<'_>) -> Result<Measure> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
 
-    client.call("get_measure", "https://api.netatmo.com/api/getmeasure", &mut params)
+    client.call("get_measure", "api/getmeasure", &params).await
 }
 
-fn de_body_values<'de, D>(deserializer: D) -> ::std::result::Result<HashMap<usize, Vec<Option<f64>>>, D::Error>
+pub(crate) fn de_body_values<'de, D>(
+    deserializer: D,
+) -> ::std::result::Result<HashMap<usize, Vec<Option<f64>>>, D::Error>
 where
     D: Deserializer<'de>,
 {
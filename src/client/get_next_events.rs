@@ -0,0 +1,283 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{get_events::EventsList, get_home_data::Event},
+    errors::Result,
+};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    event_id: &'a str,
+    size: Option<usize>,
+}
+
+impl<'a> Parameters<'a> {
+    /// Returns the events older than `event_id`
+    pub fn new(home_id: &'a str, event_id: &'a str) -> Self {
+        Parameters {
+            home_id,
+            event_id,
+            size: None,
+        }
+    }
+
+    pub fn size(self, size: usize) -> Self {
+        Parameters {
+            size: Some(size),
+            ..self
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("home_id", p.home_id.to_string());
+        m.insert("event_id", p.event_id.to_string());
+        if let Some(size) = p.size {
+            m.insert("size", size.to_string());
+        }
+
+        m
+    }
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#getnextevents
+#[cfg(feature = "blocking")]
+pub(crate) fn get_next_events(client: &AuthenticatedClient, parameters: &Parameters) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_next_events", "api/getnextevents", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_next_events_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<EventsList> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_next_events", "api/getnextevents", &params).await
+}
+
+/// Paging state shared by `EventHistory` and `AsyncEventHistory`
+#[derive(Debug)]
+struct Pager {
+    home_id: String,
+    /// Oldest event seen so far; the next page starts right before it
+    event_id: String,
+    /// Ids of all events seen so far to stop on pages repeating them
+    seen: HashSet<String>,
+    size: Option<usize>,
+    until: Option<i64>,
+    events: VecDeque<Event>,
+    done: bool,
+}
+
+impl Pager {
+    fn new(home_id: &str, event_id: &str) -> Self {
+        Pager {
+            home_id: home_id.to_string(),
+            event_id: event_id.to_string(),
+            seen: std::iter::once(event_id.to_string()).collect(),
+            size: None,
+            until: None,
+            events: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the next buffered event unless it is older than the cutoff
+    fn pop(&mut self) -> Option<Event> {
+        let event = self.events.pop_front()?;
+        if self.until.map(|until| event.time < until).unwrap_or(false) {
+            self.done = true;
+            self.events.clear();
+            return None;
+        }
+
+        Some(event)
+    }
+
+    fn parameters(&self) -> Parameters<'_> {
+        let parameters = Parameters::new(&self.home_id, &self.event_id);
+        match self.size {
+            Some(size) => parameters.size(size),
+            None => parameters,
+        }
+    }
+
+    fn push(&mut self, events: Vec<Event>) {
+        let seen = &mut self.seen;
+        let events: Vec<Event> = events.into_iter().filter(|x| seen.insert(x.id.clone())).collect();
+        match events.last() {
+            Some(last) => self.event_id = last.id.clone(),
+            None => self.done = true,
+        }
+        self.events.extend(events);
+    }
+}
+
+/// Iterates backwards in time over the events of a home via `getnextevents`, one page per API call
+///
+/// Iteration starts with the event right before `event_id`, e.g., the oldest event returned by `gethomedata`, and
+/// ends when Netatmo has no older events or only repeats seen ones, the cutoff set by `until` is reached or a call
+/// fails.
+#[cfg(feature = "blocking")]
+pub struct EventHistory<'a> {
+    client: &'a AuthenticatedClient,
+    pager: Pager,
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> EventHistory<'a> {
+    pub fn new(client: &'a AuthenticatedClient, home_id: &str, event_id: &str) -> Self {
+        EventHistory {
+            client,
+            pager: Pager::new(home_id, event_id),
+        }
+    }
+
+    /// Stops at the first event older than this Unix timestamp
+    pub fn until(mut self, until: i64) -> Self {
+        self.pager.until = Some(until);
+        self
+    }
+
+    /// Number of events to request per page
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.pager.size = Some(size);
+        self
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl Iterator for EventHistory<'_> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pager.pop() {
+                return Some(Ok(event));
+            }
+            if self.pager.done {
+                return None;
+            }
+            match get_next_events(self.client, &self.pager.parameters()) {
+                Ok(events) => self.pager.push(events.body.events_list),
+                Err(e) => {
+                    self.pager.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Asynchronous counterpart of `EventHistory`
+#[cfg(feature = "async")]
+pub struct AsyncEventHistory<'a> {
+    client: &'a AsyncAuthenticatedClient,
+    pager: Pager,
+}
+
+#[cfg(feature = "async")]
+impl<'a> AsyncEventHistory<'a> {
+    pub fn new(client: &'a AsyncAuthenticatedClient, home_id: &str, event_id: &str) -> Self {
+        AsyncEventHistory {
+            client,
+            pager: Pager::new(home_id, event_id),
+        }
+    }
+
+    /// Stops at the first event older than this Unix timestamp
+    pub fn until(mut self, until: i64) -> Self {
+        self.pager.until = Some(until);
+        self
+    }
+
+    /// Number of events to request per page
+    pub fn page_size(mut self, size: usize) -> Self {
+        self.pager.size = Some(size);
+        self
+    }
+
+    /// Returns the next older event or `None` once iteration has ended
+    pub async fn next_event(&mut self) -> Option<Result<Event>> {
+        loop {
+            if let Some(event) = self.pager.pop() {
+                return Some(Ok(event));
+            }
+            if self.pager.done {
+                return None;
+            }
+            match get_next_events_async(self.client, &self.pager.parameters()).await {
+                Ok(events) => self.pager.push(events.body.events_list),
+                Err(e) => {
+                    self.pager.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod event_history {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{client, form, now, token},
+        };
+
+        const PAGE_1: &str = r#"{"body": {"events_list": [
+  {"id": "e3", "type": "movement", "time": 300, "camera_id": "c"},
+  {"id": "e2", "type": "person", "time": 200, "camera_id": "c"}
+]}, "status": "ok", "time_server": 1580000000}"#;
+        const PAGE_2: &str = r#"{"body": {"events_list": [
+  {"id": "e1", "type": "connection", "time": 100, "camera_id": "c"}
+]}, "status": "ok", "time_server": 1580000000}"#;
+
+        #[test]
+        fn page_backwards_until_cutoff() {
+            let server = FakeServer::start(vec![(200, PAGE_1), (200, PAGE_2)]);
+            let client = client(&server, token(now()));
+
+            let events: Result<Vec<_>> = EventHistory::new(&client, "home", "e4")
+                .until(150)
+                .page_size(2)
+                .collect();
+
+            assert_that(&events.map(|x| x.into_iter().map(|e| e.id).collect::<Vec<_>>()))
+                .is_ok()
+                .is_equal_to(vec!["e3".to_string(), "e2".to_string()]);
+            let requests = server.requests();
+            assert_that(&requests[0].target).is_equal_to("/api/getnextevents".to_string());
+            assert_that(&form(&requests[0])["event_id"]).is_equal_to("e4".to_string());
+            assert_that(&form(&requests[1])["event_id"]).is_equal_to("e2".to_string());
+        }
+
+        #[test]
+        fn stop_on_repeated_page() {
+            let server = FakeServer::start(vec![(200, PAGE_1), (200, PAGE_1)]);
+            let client = client(&server, token(now()));
+
+            let events: Result<Vec<_>> = EventHistory::new(&client, "home", "e4").collect();
+
+            assert_that(&events.map(|x| x.into_iter().map(|e| e.id).collect::<Vec<_>>()))
+                .is_ok()
+                .is_equal_to(vec!["e3".to_string(), "e2".to_string()]);
+            assert_that(&server.requests()).has_length(2);
+        }
+    }
+}
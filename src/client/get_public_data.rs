@@ -0,0 +1,276 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::response::{self, unmapped, ApiResponse, Warning},
+    errors::Result,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// Bounding box query for public weather stations, from north east to south west
+pub struct Parameters<'a> {
+    lat_ne: f64,
+    lon_ne: f64,
+    lat_sw: f64,
+    lon_sw: f64,
+    required_data: Option<&'a [RequiredData]>,
+    filter: Option<bool>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(lat_ne: f64, lon_ne: f64, lat_sw: f64, lon_sw: f64) -> Self {
+        Parameters {
+            lat_ne,
+            lon_ne,
+            lat_sw,
+            lon_sw,
+            required_data: None,
+            filter: None,
+        }
+    }
+
+    /// Returns only stations providing all of these measurements
+    pub fn required_data(self, required_data: &'a [RequiredData]) -> Self {
+        Parameters {
+            required_data: Some(required_data),
+            ..self
+        }
+    }
+
+    /// Excludes stations with abnormal temperatures
+    pub fn filter(self, filter: bool) -> Self {
+        Parameters {
+            filter: Some(filter),
+            ..self
+        }
+    }
+}
+
+pub enum RequiredData {
+    Temperature,
+    Humidity,
+    Pressure,
+    Rain,
+    Wind,
+}
+
+impl fmt::Display for RequiredData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RequiredData::Temperature => "temperature",
+            RequiredData::Humidity => "humidity",
+            RequiredData::Pressure => "pressure",
+            RequiredData::Rain => "rain",
+            RequiredData::Wind => "wind",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut m = HashMap::default();
+        m.insert("lat_ne", p.lat_ne.to_string());
+        m.insert("lon_ne", p.lon_ne.to_string());
+        m.insert("lat_sw", p.lat_sw.to_string());
+        m.insert("lon_sw", p.lon_sw.to_string());
+        if let Some(required_data) = p.required_data {
+            let required_data = required_data
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .as_slice()
+                .join(",");
+            m.insert("required_data", required_data);
+        }
+        if let Some(filter) = p.filter {
+            m.insert("filter", filter.to_string());
+        }
+
+        m
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicData {
+    pub body: Vec<Station>,
+    pub status: String,
+    pub time_exec: f64,
+    pub time_server: u64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+}
+
+impl ApiResponse for PublicData {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["body"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Station {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub place: Place,
+    pub mark: Option<u64>,
+    /// Measures by module id; the station itself reports pressure
+    pub measures: HashMap<String, Measures>,
+    #[serde(default)]
+    pub modules: Vec<String>,
+    /// Module types like `NAModule1` by module id
+    #[serde(default)]
+    pub module_types: HashMap<String, String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    /// Longitude and latitude
+    pub location: Vec<f64>,
+    pub timezone: String,
+    pub country: Option<String>,
+    pub altitude: Option<f64>,
+    pub city: Option<String>,
+    pub street: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+/// Measures of one module; outdoor modules report `res` and `type`, rain and wind gauges their own fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measures {
+    /// Values by Unix timestamp in the order of `type`
+    #[serde(default)]
+    pub res: HashMap<String, Vec<Option<f64>>>,
+    #[serde(rename = "type", default)]
+    pub types: Vec<String>,
+    pub rain_60min: Option<f64>,
+    pub rain_24h: Option<f64>,
+    pub rain_live: Option<f64>,
+    pub rain_timeutc: Option<u64>,
+    pub wind_strength: Option<f64>,
+    pub wind_angle: Option<f64>,
+    pub gust_strength: Option<f64>,
+    pub gust_angle: Option<f64>,
+    pub wind_timeutc: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Measures {
+    /// Returns the most recent value of measurement `type_name`, e.g., `temperature`, with its timestamp
+    pub fn latest(&self, type_name: &str) -> Option<(u64, f64)> {
+        let pos = self.types.iter().position(|x| x == type_name)?;
+        self.res
+            .iter()
+            .filter_map(|(time, values)| Some((time.parse().ok()?, values.get(pos).copied().flatten()?)))
+            .max_by_key(|(time, _)| *time)
+    }
+}
+
+unmapped! {
+    PublicData { body };
+    Station { place, measures };
+    Place {};
+    Measures {};
+}
+
+// cf. https://dev.netatmo.com/apidocumentation/weather#getpublicdata
+#[cfg(feature = "blocking")]
+pub(crate) fn get_public_data(client: &AuthenticatedClient, parameters: &Parameters) -> Result<PublicData> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_public_data", "api/getpublicdata", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_public_data_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<PublicData> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    client.call("get_public_data", "api/getpublicdata", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod get_public_data {
+        use super::*;
+
+        #[test]
+        fn parse_response() {
+            let json = r#"{
+  "body": [
+    {
+      "_id": "70:ee:50:00:00:01",
+      "place": {
+        "location": [2.35, 48.85],
+        "timezone": "Europe/Paris",
+        "country": "FR",
+        "altitude": 35,
+        "city": "Paris",
+        "street": "Rue de Rivoli"
+      },
+      "mark": 10,
+      "measures": {
+        "70:ee:50:00:00:01": {"res": {"1580000000": [1012.3]}, "type": ["pressure"]},
+        "02:00:00:00:00:01": {"res": {"1579999700": [11.8, 80], "1580000000": [12.3, 79]}, "type": ["temperature", "humidity"]},
+        "05:00:00:00:00:01": {"rain_60min": 0.2, "rain_24h": 3.1, "rain_live": 0, "rain_timeutc": 1580000000},
+        "06:00:00:00:00:01": {"wind_strength": 5, "wind_angle": 200, "gust_strength": 10, "gust_angle": 210, "wind_timeutc": 1580000000}
+      },
+      "modules": ["02:00:00:00:00:01", "05:00:00:00:00:01", "06:00:00:00:00:01"],
+      "module_types": {"02:00:00:00:00:01": "NAModule1", "05:00:00:00:00:01": "NAModule3", "06:00:00:00:00:01": "NAModule2"}
+    }
+  ],
+  "status": "ok",
+  "time_exec": 0.1,
+  "time_server": 1580000000
+}"#;
+
+            let public_data: PublicData = serde_json::from_str(json).expect("Failed to parse public data");
+
+            let station = &public_data.body[0];
+            assert_that(&station.place.city).is_equal_to(Some("Paris".to_string()));
+            assert_that(&station.measures["02:00:00:00:00:01"].latest("temperature"))
+                .is_equal_to(Some((1580000000, 12.3)));
+            assert_that(&station.measures["05:00:00:00:00:01"].rain_24h).is_equal_to(Some(3.1));
+            assert_that(&station.measures["06:00:00:00:00:01"].gust_angle).is_equal_to(Some(210.0));
+            assert_that(&public_data.unmapped_fields()).is_empty();
+        }
+
+        #[test]
+        fn parameters() {
+            let required_data = [RequiredData::Temperature, RequiredData::Rain];
+            let parameters = Parameters::new(48.9, 2.4, 48.8, 2.3)
+                .required_data(&required_data)
+                .filter(true);
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params["lat_ne"]).is_equal_to("48.9".to_string());
+            assert_that(&params["lon_sw"]).is_equal_to("2.3".to_string());
+            assert_that(&params["required_data"]).is_equal_to("temperature,rain".to_string());
+            assert_that(&params["filter"]).is_equal_to("true".to_string());
+        }
+    }
+}
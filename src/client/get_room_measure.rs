@@ -0,0 +1,241 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{
+        get_measure::{de_body_values, Scale},
+        response::ApiResponse,
+    },
+    errors::Result,
+};
+
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    room_id: &'a str,
+    scale: Scale,
+    types: &'a [Type],
+    date_begin: Option<usize>,
+    date_end: Option<usize>,
+    limit: Option<usize>,
+    real_time: Option<bool>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(home_id: &'a str, room_id: &'a str, scale: Scale, types: &'a [Type]) -> Self {
+        Parameters {
+            home_id,
+            room_id,
+            scale,
+            types,
+            date_begin: None,
+            date_end: None,
+            limit: None,
+            real_time: None,
+        }
+    }
+
+    pub fn date_begin(self, date_begin: usize) -> Self {
+        Parameters {
+            date_begin: Some(date_begin),
+            ..self
+        }
+    }
+
+    pub fn date_end(self, date_end: usize) -> Self {
+        Parameters {
+            date_end: Some(date_end),
+            ..self
+        }
+    }
+
+    /// Maximum number of measurements; Netatmo allows up to 1024
+    pub fn limit(self, limit: usize) -> Self {
+        Parameters {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub fn real_time(self, real_time: bool) -> Self {
+        Parameters {
+            real_time: Some(real_time),
+            ..self
+        }
+    }
+}
+
+/// Measurement of a room; `Min*`, `Max*` and `Date*` types require a scale of at least `Scale::Min30`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Temperature,
+    SpTemperature,
+    HeatingPowerRequest,
+    BoilerOn,
+    BoilerOff,
+    MinTemp,
+    MaxTemp,
+    DateMinTemp,
+    DateMaxTemp,
+    MinSpTemperature,
+    MaxSpTemperature,
+    DateMinSpTemperature,
+    DateMaxSpTemperature,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Type::Temperature => "temperature",
+            Type::SpTemperature => "sp_temperature",
+            Type::HeatingPowerRequest => "heating_power_request",
+            Type::BoilerOn => "boileron",
+            Type::BoilerOff => "boileroff",
+            Type::MinTemp => "min_temp",
+            Type::MaxTemp => "max_temp",
+            Type::DateMinTemp => "date_min_temp",
+            Type::DateMaxTemp => "date_max_temp",
+            Type::MinSpTemperature => "min_sp_temperature",
+            Type::MaxSpTemperature => "max_sp_temperature",
+            Type::DateMinSpTemperature => "date_min_sp_temperature",
+            Type::DateMaxSpTemperature => "date_max_sp_temperature",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let types = p.types.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+        let mut m = HashMap::default();
+        m.insert("home_id", p.home_id.to_string());
+        m.insert("room_id", p.room_id.to_string());
+        m.insert("scale", p.scale.to_string());
+        m.insert("type", types);
+        if let Some(date_begin) = p.date_begin {
+            m.insert("date_begin", date_begin.to_string());
+        }
+        if let Some(date_end) = p.date_end {
+            m.insert("date_end", date_end.to_string());
+        }
+        if let Some(limit) = p.limit {
+            m.insert("limit", limit.to_string());
+        }
+        m.insert("optimize", "false".to_string());
+        if let Some(real_time) = p.real_time {
+            m.insert("real_time", real_time.to_string());
+        }
+
+        m
+    }
+}
+
+/// Measurements of a room, one series per requested type
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomMeasure {
+    /// Same as `get_home_status::Room::id`
+    pub room_id: String,
+    pub series: Vec<Series>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub measure_type: Type,
+    /// Pairs of Unix timestamp and value ordered by time
+    pub values: Vec<(usize, Option<f64>)>,
+}
+
+impl RoomMeasure {
+    pub fn series(&self, measure_type: Type) -> Option<&Series> {
+        self.series.iter().find(|x| x.measure_type == measure_type)
+    }
+
+    fn new(parameters: &Parameters, response: Response) -> Self {
+        let mut times: Vec<_> = response.values.into_iter().collect();
+        times.sort_by_key(|(time, _)| *time);
+        let series = parameters
+            .types
+            .iter()
+            .enumerate()
+            .map(|(i, measure_type)| Series {
+                measure_type: *measure_type,
+                values: times
+                    .iter()
+                    .map(|(time, values)| (*time, values.get(i).copied().flatten()))
+                    .collect(),
+            })
+            .collect();
+
+        RoomMeasure {
+            room_id: parameters.room_id.to_string(),
+            series,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    #[serde(rename = "body", deserialize_with = "de_body_values")]
+    values: HashMap<usize, Vec<Option<f64>>>,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/energy#getroommeasure
+#[cfg(feature = "blocking")]
+pub(crate) fn get_room_measure(client: &AuthenticatedClient, parameters: &Parameters) -> Result<RoomMeasure> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    let response = client.call("get_room_measure", "api/getroommeasure", &params)?;
+
+    Ok(RoomMeasure::new(parameters, response))
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_room_measure_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<RoomMeasure> {
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+    let response = client.call("get_room_measure", "api/getroommeasure", &params).await?;
+
+    Ok(RoomMeasure::new(parameters, response))
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    const BODY: &str = r#"{
+  "body": {"1580003600": [20.5, 21], "1580000000": [19.8, null]},
+  "status": "ok",
+  "time_exec": 0.04,
+  "time_server": 1580007200
+}"#;
+
+    mod room_measure {
+        use super::*;
+
+        #[test]
+        fn split_into_series_ordered_by_time() {
+            let types = [Type::Temperature, Type::SpTemperature];
+            let parameters = Parameters::new("home", "2255", Scale::Min30, &types);
+            let response: Response = serde_json::from_str(BODY).unwrap();
+
+            let measure = RoomMeasure::new(&parameters, response);
+
+            assert_that(&measure.room_id).is_equal_to("2255".to_string());
+            assert_that(&measure.series(Type::SpTemperature).map(|x| x.values.clone()))
+                .is_some()
+                .is_equal_to(vec![(1_580_000_000, None), (1_580_003_600, Some(21.0))]);
+            assert_that(&measure.series(Type::BoilerOn)).is_none();
+        }
+    }
+}
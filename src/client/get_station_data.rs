@@ -1,8 +1,16 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::{client::AuthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::response::{self, unmapped, ApiResponse, Warning},
+    errors::Result,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StationData {
@@ -10,12 +18,31 @@ pub struct StationData {
     pub status: String,
     pub time_exec: f64,
     pub time_server: u64,
+    /// Elements skipped in lenient mode
+    #[serde(skip)]
+    pub warnings: Vec<Warning>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ApiResponse for StationData {
+    const LENIENT_ARRAYS: &'static [&'static str] = &["devices", "modules"];
+
+    fn set_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings = warnings;
+    }
+
+    fn unmapped_fields(&self) -> Vec<String> {
+        response::unmapped_fields(self)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Body {
     pub devices: Vec<Device>,
     pub user: User,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,6 +66,8 @@ pub struct Device {
     #[serde(default)]
     pub modules: Vec<Module>,
     pub place: Place,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,6 +92,8 @@ pub struct DashboardData {
     pub pressure_trend: Option<String>,
     pub temp_trend: Option<String>,
     pub time_utc: Option<u64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,6 +113,8 @@ pub struct Module {
     pub rf_status: u64,
     #[serde(rename = "type")]
     pub type_info: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -91,12 +124,16 @@ pub struct Place {
     pub country: String,
     pub location: Vec<f64>,
     pub timezone: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User {
     pub administrative: Administrative,
     pub mail: String,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -108,28 +145,53 @@ pub struct Administrative {
     pub reg_locale: String,
     pub unit: u64,
     pub windunit: u64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
-pub(crate) fn get_station_data(client: &AuthenticatedClient, device_id: &str) -> Result<StationData> {
+unmapped! {
+    StationData { body };
+    Body { devices, user };
+    Device { dashboard_data, modules, place };
+    DashboardData {};
+    Module { dashboard_data };
+    Place {};
+    User { administrative };
+    Administrative {};
+}
+
+fn params(device_id: &str) -> HashMap<&str, &str> {
     let mut params: HashMap<&str, &str> = HashMap::default();
     params.insert("device_id", device_id);
 
-    client.call(
-        "get_station_data",
-        "https://api.netatmo.com/api/getstationsdata",
-        &mut params,
-    )
+    params
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn get_station_data(client: &AuthenticatedClient, device_id: &str) -> Result<StationData> {
+    client.call("get_station_data", "api/getstationsdata", &params(device_id))
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn get_station_data_async(client: &AsyncAuthenticatedClient, device_id: &str) -> Result<StationData> {
+    client
+        .call("get_station_data", "api/getstationsdata", &params(device_id))
+        .await
 }
 
+#[cfg(feature = "blocking")]
 pub(crate) fn get_homecoachs_data(client: &AuthenticatedClient, device_id: &str) -> Result<StationData> {
-    let mut params: HashMap<&str, &str> = HashMap::default();
-    params.insert("device_id", device_id);
+    client.call("get_homecoachs_data", "api/gethomecoachsdata", &params(device_id))
+}
 
-    client.call(
-        "get_homecoachs_data",
-        "https://api.netatmo.com/api/gethomecoachsdata",
-        &mut params,
-    )
+#[cfg(feature = "async")]
+pub(crate) async fn get_homecoachs_data_async(
+    client: &AsyncAuthenticatedClient,
+    device_id: &str,
+) -> Result<StationData> {
+    client
+        .call("get_homecoachs_data", "api/gethomecoachsdata", &params(device_id))
+        .await
 }
 
 #[cfg(test)]
@@ -240,10 +302,148 @@ mod test {
   "time_server": 1556451492
 }"#;
 
-            let station_data: ::std::result::Result<StationData, _> = serde_json::from_str(&json);
+            let station_data: ::std::result::Result<StationData, _> = serde_json::from_str(json);
 
             assert_that(&station_data).is_ok();
         }
+
+        #[test]
+        fn keep_unknown_fields() {
+            let json = r#"{
+  "body": {
+    "devices": [],
+    "user": {
+      "mail": "user@example.com",
+      "administrative": {"lang": "en", "reg_locale": "en-US", "unit": 0, "windunit": 0, "feel_like_algo": 0}
+    },
+    "cipher_id": "enc:16:icj48gjlkt399g"
+  },
+  "status": "ok",
+  "time_exec": 0.1,
+  "time_server": 1580000000
+}"#;
+
+            let station_data: StationData = serde_json::from_str(json).expect("Failed to parse station data");
+            let serialized = serde_json::to_value(&station_data).expect("Failed to serialize station data");
+
+            assert_that(&station_data.unmapped_fields()).is_equal_to(vec!["body.cipher_id".to_string()]);
+            assert_that(&serialized["body"]["cipher_id"].as_str()).is_equal_to(Some("enc:16:icj48gjlkt399g"));
+        }
+    }
+
+    mod handle_response {
+        use super::*;
+        use crate::{
+            client::{handle_response, Config},
+            errors::ErrorKind,
+        };
+        use reqwest::StatusCode;
+
+        const STATION_DATA: &str = r#"{
+  "body": {
+    "devices": [
+      {
+        "_id": "12:34:56:78:90:AB",
+        "co2_calibrating": false,
+        "dashboard_data": {"Temperature": 20.3, "time_utc": 1556451224},
+        "data_type": ["Temperature"],
+        "date_setup": 1556295333,
+        "firmware": 140,
+        "last_setup": 1556295333,
+        "last_status_store": 1556451233,
+        "module_name": "Inside",
+        "modules": [
+          {
+            "_id": "12:34:56:78:90:CD",
+            "battery_percent": 100,
+            "battery_vp": 6190,
+            "dashboard_data": {"Temperature": 13.8, "time_utc": 1556451208},
+            "data_type": ["Temperature"],
+            "firmware": 46,
+            "last_message": 1556451228,
+            "last_seen": 1556451208,
+            "last_setup": 1556295333,
+            "module_name": "Outside",
+            "reachable": true,
+            "rf_status": 86,
+            "type": "NAModule1"
+          },
+          {
+            "_id": "12:34:56:78:90:EF",
+            "battery_percent": 80,
+            "battery_vp": 5800,
+            "dashboard_data": {"Temperature": 21.1, "time_utc": 1556451208},
+            "data_type": ["Temperature"],
+            "firmware": 46,
+            "last_message": 1556451228,
+            "last_seen": 1556451208,
+            "last_setup": 1556295333,
+            "module_name": "Bedroom",
+            "reachable": true,
+            "rf_status": 70,
+            "type": "NAModule4"
+          }
+        ],
+        "place": {"altitude": 50, "city": "Alert", "country": "CAN", "location": [82.5, -62.5], "timezone": "EDT"},
+        "reachable": true,
+        "station_name": "Home",
+        "type": "NAMain",
+        "wifi_status": 50
+      }
+    ],
+    "user": {
+      "administrative": {"feel_like_algo": 0, "lang": "en-US", "reg_locale": "en-US", "unit": 0, "windunit": 0},
+      "mail": "user@example.com"
+    }
+  },
+  "status": "ok",
+  "time_exec": 0.1,
+  "time_server": 1556451492
+}"#;
+
+        fn station_data<F: FnOnce(&mut Value)>(modify: F) -> String {
+            let mut json: Value = serde_json::from_str(STATION_DATA).expect("Failed to parse fixture");
+            modify(&mut json["body"]["devices"][0]["modules"][1]);
+
+            json.to_string()
+        }
+
+        #[test]
+        fn report_path_of_deserialization_failure() {
+            let json = station_data(|module| module["battery_vp"] = Value::from("low"));
+
+            let res: Result<StationData> =
+                handle_response(&Config::default(), "get_station_data", StatusCode::OK, &json);
+
+            match res.map_err(|e| e.kind().clone()) {
+                Err(ErrorKind::JsonDeserializationFailed { name, path, .. }) => {
+                    assert_that(&name).is_equal_to("get_station_data");
+                    assert_that(&path).is_equal_to("body.devices[0].modules[1].battery_vp".to_string());
+                }
+                res => panic!("Unexpected result {:?}", res),
+            }
+        }
+
+        #[test]
+        fn skip_module_without_battery_percent_in_lenient_mode() {
+            let json = station_data(|module| {
+                module.as_object_mut().map(|x| x.remove("battery_percent"));
+            });
+            let config = Config {
+                lenient: true,
+                ..Config::default()
+            };
+
+            let res: Result<StationData> = handle_response(&config, "get_station_data", StatusCode::OK, &json);
+
+            let station_data = res.expect("Failed to parse station data");
+            let modules = &station_data.body.devices[0].modules;
+            assert_that(modules).has_length(1);
+            assert_that(&modules[0].module_name).is_equal_to("Outside".to_string());
+            assert_that(&station_data.warnings).has_length(1);
+            assert_that(&station_data.warnings[0].skipped).is_equal_to("body.devices[0].modules[1]".to_string());
+            assert_that(&station_data.warnings[0].msg).contains("battery_percent");
+        }
     }
 
     mod get_homecoach_data {
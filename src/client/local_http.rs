@@ -0,0 +1,177 @@
+//! Minimal HTTP/1.1 handling for the local listeners of this crate, e.g., the OAuth2 redirect listener and the
+//! webhook server.
+
+use log::warn;
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    time::Duration,
+};
+
+/// How long a listener waits for the next bytes of a request before dropping the connection
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADER_LINES: usize = 100;
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub method: String,
+    pub target: String,
+    /// Header names are lower case
+    #[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+    pub headers: HashMap<String, String>,
+    #[cfg_attr(not(feature = "webhook"), allow(dead_code))]
+    pub body: Vec<u8>,
+}
+
+/// Accepts the next connection and bounds how long reading from it may block
+///
+/// Failures of a single connection are logged and yield `None` so that listeners keep serving; only failures of the
+/// listener itself are returned.
+pub(crate) fn accept(listener: &TcpListener, read_timeout: Duration) -> io::Result<Option<(TcpStream, SocketAddr)>> {
+    let (stream, peer) = match listener.accept() {
+        Ok(accepted) => accepted,
+        Err(ref e) if is_connection_error(e) => {
+            warn!("Failed to accept connection: {}", e);
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    if let Err(e) = stream.set_read_timeout(Some(read_timeout)) {
+        warn!("Failed to set read timeout for connection from {}: {}", peer, e);
+        return Ok(None);
+    }
+
+    Ok(Some((stream, peer)))
+}
+
+fn is_connection_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset | io::ErrorKind::Interrupted
+    )
+}
+
+pub(crate) fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err(invalid_data("malformed request line")),
+    };
+
+    let mut headers = HashMap::new();
+    for _ in 0..MAX_HEADER_LINES {
+        let line = read_line(&mut reader)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(pos) = line.find(':') {
+            let (name, value) = line.split_at(pos);
+            headers.insert(name.trim().to_lowercase(), value[1..].trim().to_string());
+        }
+    }
+
+    let content_length = match headers.get("content-length") {
+        Some(len) => len
+            .parse::<usize>()
+            .map_err(|_| invalid_data("invalid content length"))?,
+        None => 0,
+    };
+    if content_length > MAX_BODY_SIZE {
+        return Err(invalid_data("request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH as u64).read_line(&mut line)?;
+    if line.len() == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Err(invalid_data("line too long"));
+    }
+
+    Ok(line)
+}
+
+pub(crate) fn write_response(stream: &mut TcpStream, status: u16, content_type: &str, body: &[u8]) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason_phrase(status),
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Serves canned responses to a fixed number of requests on a random local port
+#[cfg(test)]
+pub(crate) struct FakeServer {
+    url: String,
+    handle: std::thread::JoinHandle<Vec<Request>>,
+}
+
+#[cfg(test)]
+impl FakeServer {
+    pub fn start(responses: Vec<(u16, &str)>) -> FakeServer {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind fake server");
+        let url = format!("http://{}", listener.local_addr().expect("Failed to get local address"));
+        let responses: Vec<(u16, String)> = responses.into_iter().map(|(s, b)| (s, b.to_string())).collect();
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().expect("Failed to accept connection");
+                requests.push(read_request(&mut stream).expect("Failed to read request"));
+                write_response(&mut stream, status, "application/json", body.as_bytes())
+                    .expect("Failed to write response");
+            }
+            requests
+        });
+
+        FakeServer { url, handle }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Waits until all canned responses have been served and returns the received requests
+    pub fn requests(self) -> Vec<Request> {
+        self.handle.join().expect("Fake server panicked")
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    client::get_camera_picture::Picture,
+    errors::{ErrorKind, Result},
+};
+
+use failure::Fail;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Default for `PictureCache::retention`
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Default for `PictureCache::purge_interval`
+pub const DEFAULT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// File extensions of cached pictures and their content types; pictures of other types are not cached
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+];
+
+const TMP_EXTENSION: &str = "tmp";
+
+/// Numbers the temporary files of concurrent stores within this process
+static TMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Keeps downloaded camera pictures in a directory so that each one is fetched only once
+///
+/// Pictures older than the retention period count as missing. Storing a picture removes them at most once per purge
+/// interval; `purge` removes them right away. Only pictures whose content type maps to a file extension are cached, so
+/// cached pictures keep the content type of their download. The directory should be dedicated to the cache. Async
+/// clients access it on tokio's blocking thread pool.
+#[derive(Debug, Clone)]
+pub struct PictureCache {
+    dir: PathBuf,
+    retention: Duration,
+    purge_interval: Duration,
+    /// Shared by clones so that clients using copies of the same cache purge only once per interval
+    last_purge: Arc<Mutex<Option<Instant>>>,
+}
+
+impl PictureCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        PictureCache {
+            dir: dir.into(),
+            retention: DEFAULT_RETENTION,
+            purge_interval: DEFAULT_PURGE_INTERVAL,
+            last_purge: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Sets how long pictures are kept; defaults to `DEFAULT_RETENTION`
+    pub fn retention(self, retention: Duration) -> Self {
+        PictureCache { retention, ..self }
+    }
+
+    /// Sets how often storing a picture removes expired ones; defaults to `DEFAULT_PURGE_INTERVAL`
+    pub fn purge_interval(self, purge_interval: Duration) -> Self {
+        PictureCache { purge_interval, ..self }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn load(&self, image_id: &str, key: &str) -> Result<Option<Picture>> {
+        for (extension, content_type) in EXTENSIONS {
+            let path = self.path(image_id, key, extension);
+            match self.is_expired(&path) {
+                Ok(true) => return Ok(None),
+                Ok(false) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.context(ErrorKind::PictureCacheFailed).into()),
+            }
+            let bytes = fs::read(&path).map_err(|e| e.context(ErrorKind::PictureCacheFailed))?;
+            return Ok(Some(Picture {
+                content_type: content_type.to_string(),
+                bytes,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Stores `picture` after removing expired pictures if the purge interval has passed
+    ///
+    /// Pictures of content types without a file extension are skipped.
+    pub fn store(&self, image_id: &str, key: &str, picture: &Picture) -> Result<()> {
+        if self.is_purge_due() {
+            self.purge()?;
+        }
+        let extension = match EXTENSIONS
+            .iter()
+            .find(|(_, content_type)| *content_type == picture.content_type)
+        {
+            Some((extension, _)) => *extension,
+            None => return Ok(()),
+        };
+        let path = self.path(image_id, key, extension);
+        let n = TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = path.with_extension(format!("{}.{}.{}.{}", extension, process::id(), n, TMP_EXTENSION));
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&tmp_path, &picture.bytes))
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                e.context(ErrorKind::PictureCacheFailed)
+            })?;
+
+        Ok(())
+    }
+
+    /// Removes pictures older than the retention period and returns how many have been removed
+    ///
+    /// Temporary files older than the retention period, e.g., left behind by interrupted stores, are removed, too.
+    pub fn purge(&self) -> Result<usize> {
+        *self.last_purge.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.context(ErrorKind::PictureCacheFailed).into()),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry.map_err(|e| e.context(ErrorKind::PictureCacheFailed))?.path();
+            let extension = path.extension().unwrap_or_default();
+            let is_picture = EXTENSIONS.iter().any(|(x, _)| extension == *x);
+            let is_tmp = extension == TMP_EXTENSION;
+            if (is_picture || is_tmp) && self.is_expired(&path).unwrap_or(false) {
+                fs::remove_file(&path).map_err(|e| e.context(ErrorKind::PictureCacheFailed))?;
+                if is_picture {
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    fn is_purge_due(&self) -> bool {
+        match *self.last_purge.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(last_purge) => last_purge.elapsed() >= self.purge_interval,
+            None => true,
+        }
+    }
+
+    fn path(&self, image_id: &str, key: &str, extension: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}-{}.{}", encode(image_id), encode(key), extension))
+    }
+
+    fn is_expired(&self, path: &Path) -> io::Result<bool> {
+        let modified = fs::metadata(path)?.modified()?;
+        let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+
+        Ok(age > self.retention)
+    }
+}
+
+/// Hex encodes ids and keys so that they cannot escape the cache directory and distinct ones never share a file
+fn encode(s: &str) -> String {
+    s.bytes().map(|x| format!("{:02x}", x)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    fn cache(name: &str) -> PictureCache {
+        let dir = std::env::temp_dir().join(format!("netatmo-rs-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        PictureCache::new(dir)
+    }
+
+    fn picture() -> Picture {
+        Picture {
+            content_type: "image/jpeg".to_string(),
+            bytes: vec![0xFF, 0xD8, 0xFF, 0xE0],
+        }
+    }
+
+    #[test]
+    fn store_and_load() {
+        let cache = cache("store_and_load");
+
+        cache
+            .store("5e2f0003", "g7h8i9", &picture())
+            .expect("Failed to store picture");
+        let loaded = cache.load("5e2f0003", "g7h8i9").expect("Failed to load picture");
+
+        assert_that(&loaded).is_some().is_equal_to(picture());
+        assert_that(&cache.dir().join("3565326630303033-673768386939.jpg").exists()).is_true();
+    }
+
+    #[test]
+    fn keep_content_type() {
+        let cache = cache("keep_content_type");
+        let gif = Picture {
+            content_type: "image/gif".to_string(),
+            bytes: b"GIF89a".to_vec(),
+        };
+        let bmp = Picture {
+            content_type: "image/bmp".to_string(),
+            bytes: b"BM".to_vec(),
+        };
+
+        cache
+            .store("5e2f0003", "g7h8i9", &gif)
+            .expect("Failed to store picture");
+        cache
+            .store("5e2f0004", "j1k2l3", &bmp)
+            .expect("Failed to store picture");
+
+        assert_that(&cache.load("5e2f0003", "g7h8i9").expect("Failed to load picture"))
+            .is_some()
+            .is_equal_to(gif);
+        assert_that(&cache.load("5e2f0004", "j1k2l3").expect("Failed to load picture")).is_none();
+    }
+
+    #[test]
+    fn store_concurrently() {
+        let cache = cache("store_concurrently");
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..20 {
+                        cache
+                            .store("5e2f0003", "g7h8i9", &picture())
+                            .expect("Failed to store picture");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("Failed to join storing thread");
+        }
+        let files: Vec<_> = fs::read_dir(cache.dir())
+            .expect("Failed to read directory")
+            .map(|x| x.expect("Failed to read entry").file_name())
+            .collect();
+
+        assert_that(&cache.load("5e2f0003", "g7h8i9").expect("Failed to load picture"))
+            .is_some()
+            .is_equal_to(picture());
+        assert_that(&files).has_length(1);
+    }
+
+    #[test]
+    fn load_missing() {
+        let cache = cache("load_missing");
+
+        let loaded = cache.load("5e2f0003", "g7h8i9").expect("Failed to load picture");
+
+        assert_that(&loaded).is_none();
+    }
+
+    #[test]
+    fn purge_expired_pictures() {
+        let cache = cache("purge_expired_pictures").retention(Duration::from_secs(0));
+        cache
+            .store("5e2f0003", "g7h8i9", &picture())
+            .expect("Failed to store picture");
+        std::thread::sleep(Duration::from_millis(10));
+
+        let loaded = cache.load("5e2f0003", "g7h8i9").expect("Failed to load picture");
+        let removed = cache.purge().expect("Failed to purge cache");
+
+        assert_that(&loaded).is_none();
+        assert_that(&removed).is_equal_to(1);
+    }
+
+    #[test]
+    fn purge_leftover_temporary_files() {
+        let cache = cache("purge_leftover_temporary_files").retention(Duration::from_secs(0));
+        let tmp_path = cache.dir().join("3565326630303033-673768386939.jpg.1.0.tmp");
+        fs::create_dir_all(cache.dir()).expect("Failed to create directory");
+        fs::write(&tmp_path, b"\xFF").expect("Failed to write temporary file");
+        std::thread::sleep(Duration::from_millis(10));
+
+        let removed = cache.purge().expect("Failed to purge cache");
+
+        assert_that(&removed).is_equal_to(0);
+        assert_that(&tmp_path.exists()).is_false();
+    }
+
+    #[test]
+    fn purge_once_per_interval() {
+        let cache = cache("purge_once_per_interval").retention(Duration::from_secs(0));
+        cache
+            .store("5e2f0003", "g7h8i9", &picture())
+            .expect("Failed to store picture");
+        std::thread::sleep(Duration::from_millis(10));
+
+        cache
+            .store("5e2f0004", "j1k2l3", &picture())
+            .expect("Failed to store picture");
+
+        assert_that(&cache.dir().join("3565326630303033-673768386939.jpg").exists()).is_true();
+        assert_that(&cache.purge_interval(Duration::from_secs(0)).purge()).is_ok_containing(2);
+    }
+
+    #[test]
+    fn encode_file_names() {
+        let cache = cache("encode_file_names");
+
+        assert_that(&encode("../etc/passwd")).is_equal_to("2e2e2f6574632f706173737764".to_string());
+        assert_that(&cache.path("a-b", "c", "jpg")).is_not_equal_to(cache.path("a_b", "c", "jpg"));
+        assert_that(&cache.path("a", "b-c", "jpg")).is_not_equal_to(cache.path("a-b", "c", "jpg"));
+    }
+}
@@ -0,0 +1,266 @@
+use crate::errors::{ErrorKind, Result};
+
+use std::{
+    cmp,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Netatmo's per-user quotas: 50 requests per 10 seconds and 500 requests per hour
+pub const NETATMO_LIMITS: [Limit; 2] = [
+    Limit {
+        requests: 50,
+        period: Duration::from_secs(10),
+    },
+    Limit {
+        requests: 500,
+        period: Duration::from_secs(3600),
+    },
+];
+
+/// What a client does when the rate limit is exhausted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Waits until a request is available again
+    Block,
+    /// Fails with `ErrorKind::RateLimited` right away
+    FailFast,
+}
+
+/// Allows `requests` requests per `period`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+/// Requests currently left of a `Limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub limit: Limit,
+    pub remaining: u32,
+}
+
+/// Token bucket limiter keeping API calls within Netatmo's quotas
+///
+/// Clients of the same user should share one limiter via `Arc`, because the quotas apply per user.
+#[derive(Debug)]
+pub struct RateLimiter {
+    mode: RateLimitMode,
+    buckets: Mutex<Vec<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: Limit,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: Limit) -> Self {
+        Bucket {
+            limit,
+            tokens: limit.requests as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        let rate = self.limit.requests as f64 / self.limit.period.as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(self.limit.requests as f64);
+        self.updated = now;
+    }
+
+    /// Time until the next token becomes available
+    fn wait_time(&self) -> Duration {
+        let missing = (1.0 - self.tokens).max(0.0);
+        self.limit.period.mul_f64(missing / self.limit.requests as f64)
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter::new(RateLimitMode::Block)
+    }
+}
+
+impl RateLimiter {
+    /// Creates a limiter for `NETATMO_LIMITS`
+    pub fn new(mode: RateLimitMode) -> Self {
+        RateLimiter::with_limits(mode, &NETATMO_LIMITS)
+    }
+
+    pub fn with_limits(mode: RateLimitMode, limits: &[Limit]) -> Self {
+        let buckets = limits
+            .iter()
+            .filter(|x| x.requests > 0 && x.period > Duration::from_secs(0))
+            .map(|x| Bucket::new(*x))
+            .collect();
+        RateLimiter {
+            mode,
+            buckets: Mutex::new(buckets),
+        }
+    }
+
+    pub fn mode(&self) -> RateLimitMode {
+        self.mode
+    }
+
+    /// Returns the requests left for each limit
+    pub fn budget(&self) -> Vec<Budget> {
+        let now = Instant::now();
+        self.lock_buckets()
+            .iter_mut()
+            .map(|bucket| {
+                bucket.refill(now);
+                Budget {
+                    limit: bucket.limit,
+                    remaining: bucket.tokens.floor() as u32,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the number of requests that can be sent right now
+    pub fn remaining(&self) -> u32 {
+        self.budget().iter().map(|x| x.remaining).min().unwrap_or(u32::MAX)
+    }
+
+    /// Takes one request from every limit or returns how long to wait until that is possible
+    pub(crate) fn try_acquire(&self) -> std::result::Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.lock_buckets();
+        for bucket in buckets.iter_mut() {
+            bucket.refill(now);
+        }
+        let wait_time = buckets
+            .iter()
+            .map(Bucket::wait_time)
+            .fold(Duration::from_secs(0), cmp::max);
+        if wait_time > Duration::from_secs(0) {
+            return Err(wait_time);
+        }
+        for bucket in buckets.iter_mut() {
+            bucket.tokens -= 1.0;
+        }
+
+        Ok(())
+    }
+
+    /// Takes one request or returns how long to wait in `RateLimitMode::Block`; fails in `RateLimitMode::FailFast`
+    pub(crate) fn acquire_or_wait_time(&self) -> Result<Option<Duration>> {
+        match self.try_acquire() {
+            Ok(()) => Ok(None),
+            Err(retry_after) if self.mode == RateLimitMode::FailFast => {
+                Err(ErrorKind::RateLimited { retry_after }.into())
+            }
+            Err(wait_time) => Ok(Some(wait_time)),
+        }
+    }
+
+    /// Empties the shortest limit after Netatmo reported an exceeded quota despite this limiter
+    pub(crate) fn exhaust(&self) {
+        if let Some(bucket) = self.lock_buckets().iter_mut().min_by_key(|x| x.limit.period) {
+            bucket.tokens = 0.0;
+            bucket.updated = Instant::now();
+        }
+    }
+
+    fn lock_buckets(&self) -> MutexGuard<'_, Vec<Bucket>> {
+        // Buckets stay consistent even if a thread panicked while holding the lock
+        self.buckets.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::with_limits(
+            RateLimitMode::FailFast,
+            &[
+                Limit {
+                    requests: 2,
+                    period: Duration::from_secs(10),
+                },
+                Limit {
+                    requests: 5,
+                    period: Duration::from_secs(3600),
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn acquire_until_exhausted() {
+        let limiter = limiter();
+
+        assert_that(&limiter.try_acquire()).is_ok();
+        assert_that(&limiter.try_acquire()).is_ok();
+        let res = limiter.try_acquire();
+
+        assert_that(&res).is_err();
+        assert_that(&res.unwrap_err()).is_greater_than(Duration::from_secs(4));
+    }
+
+    #[test]
+    fn report_budget() {
+        let limiter = limiter();
+
+        limiter.try_acquire().expect("Failed to acquire");
+
+        let budget = limiter.budget();
+        assert_that(&budget[0].remaining).is_equal_to(1);
+        assert_that(&budget[1].remaining).is_equal_to(4);
+        assert_that(&limiter.remaining()).is_equal_to(1);
+    }
+
+    #[test]
+    fn exhaust_shortest_limit() {
+        let limiter = limiter();
+
+        limiter.exhaust();
+
+        assert_that(&limiter.remaining()).is_equal_to(0);
+        assert_that(&limiter.budget()[1].remaining).is_equal_to(5);
+    }
+
+    #[cfg(feature = "blocking")]
+    mod authenticated_client {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{now, token, STATION_DATA},
+            Netatmo, NetatmoClient,
+        };
+        use std::sync::Arc;
+
+        #[test]
+        fn fail_fast_when_exhausted() {
+            let server = FakeServer::start(vec![(200, STATION_DATA)]);
+            let limit = Limit {
+                requests: 1,
+                period: Duration::from_secs(3600),
+            };
+            let client = NetatmoClient::builder()
+                .base_url(server.url())
+                .rate_limiter(Arc::new(RateLimiter::with_limits(RateLimitMode::FailFast, &[limit])))
+                .build_with_token(token(now()))
+                .expect("Failed to build client");
+
+            assert_that(&client.get_station_data("device")).is_ok();
+            let res = client.get_station_data("device");
+
+            assert_that(&res.map_err(|e| matches!(e.kind(), ErrorKind::RateLimited { .. }))).is_err_containing(true);
+            assert_that(&client.rate_limiter().map(RateLimiter::remaining))
+                .is_some()
+                .is_equal_to(0);
+            assert_that(&server.requests()).has_length(1);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::{Path, Segment};
+use std::collections::HashMap;
+
+type DeserializeResult<T> = std::result::Result<T, serde_path_to_error::Error<serde_json::Error>>;
+
+/// Response of an API call
+///
+/// In lenient mode, elements of the arrays named by `LENIENT_ARRAYS` that fail to deserialize are skipped and
+/// reported via `set_warnings` instead of failing the whole response.
+pub trait ApiResponse: DeserializeOwned {
+    /// Names of arrays, e.g., `devices` or `modules`, whose malformed elements may be skipped
+    const LENIENT_ARRAYS: &'static [&'static str] = &[];
+
+    fn set_warnings(&mut self, _warnings: Vec<Warning>) {}
+
+    /// Returns the paths of all fields that are not modeled and therefore ended up in an `extra` map
+    ///
+    /// Responses without `extra` maps keep the default, i.e., they are excluded from strict mode.
+    fn unmapped_fields(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Walks a model to find the keys captured by its flattened `extra` maps
+pub trait Unmapped {
+    fn collect_unmapped(&self, path: &str, fields: &mut Vec<String>);
+}
+
+impl<T: Unmapped> Unmapped for Vec<T> {
+    fn collect_unmapped(&self, path: &str, fields: &mut Vec<String>) {
+        for (i, x) in self.iter().enumerate() {
+            x.collect_unmapped(&format!("{}[{}]", path, i), fields);
+        }
+    }
+}
+
+impl<T: Unmapped> Unmapped for Option<T> {
+    fn collect_unmapped(&self, path: &str, fields: &mut Vec<String>) {
+        if let Some(x) = self {
+            x.collect_unmapped(path, fields);
+        }
+    }
+}
+
+impl<T: Unmapped> Unmapped for HashMap<String, T> {
+    fn collect_unmapped(&self, path: &str, fields: &mut Vec<String>) {
+        for (key, x) in self {
+            x.collect_unmapped(&join_path(path, key), fields);
+        }
+    }
+}
+
+pub(crate) fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Returns the sorted unmapped fields of `response`; used by `ApiResponse::unmapped_fields` implementations
+pub(crate) fn unmapped_fields<T: Unmapped>(response: &T) -> Vec<String> {
+    let mut fields = Vec::new();
+    response.collect_unmapped("", &mut fields);
+    fields.sort();
+
+    fields
+}
+
+/// Implements `Unmapped` for structs with an `extra` map by descending into the listed fields
+macro_rules! unmapped {
+    ($($t:ident { $($field:ident),* });* $(;)?) => {
+        $(
+            impl $crate::client::response::Unmapped for $t {
+                #[allow(unused_variables)]
+                fn collect_unmapped(&self, path: &str, fields: &mut Vec<String>) {
+                    fields.extend(self.extra.keys().map(|x| $crate::client::response::join_path(path, x)));
+                    $(
+                        $crate::client::response::Unmapped::collect_unmapped(
+                            &self.$field,
+                            &$crate::client::response::join_path(path, stringify!($field)),
+                            fields,
+                        );
+                    )*
+                }
+            }
+        )*
+    };
+}
+pub(crate) use unmapped;
+
+/// Array element skipped in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Path of the skipped element, e.g., `body.devices[0].modules[1]`; indices of later warnings refer to the
+    /// response without the elements skipped before
+    pub skipped: String,
+    /// Path of the value that failed to deserialize, e.g., `body.devices[0].modules[1].battery_vp`
+    pub path: String,
+    pub msg: String,
+}
+
+pub(crate) fn deserialize<T: ApiResponse>(body: &str) -> DeserializeResult<T> {
+    let deserializer = &mut serde_json::Deserializer::from_str(body);
+    serde_path_to_error::deserialize(deserializer)
+}
+
+/// Deserializes `body` while skipping malformed elements of `T::LENIENT_ARRAYS`
+pub(crate) fn deserialize_lenient<T: ApiResponse>(body: &str) -> DeserializeResult<T> {
+    let mut value: Value = match serde_json::from_str(body) {
+        Ok(value) => value,
+        Err(_) => return deserialize(body),
+    };
+    let mut warnings = Vec::new();
+    loop {
+        match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(mut response) => {
+                response.set_warnings(warnings);
+                return Ok(response);
+            }
+            Err(e) => match skip_element(&mut value, e.path(), T::LENIENT_ARRAYS) {
+                Some(skipped) => warnings.push(Warning {
+                    skipped,
+                    path: e.path().to_string(),
+                    msg: e.inner().to_string(),
+                }),
+                None => return Err(e),
+            },
+        }
+    }
+}
+
+/// Removes the innermost element of a lenient array on `path` and returns its path
+fn skip_element(value: &mut Value, path: &Path, lenient_arrays: &[&str]) -> Option<String> {
+    let segments: Vec<&Segment> = path.iter().collect();
+    let pos = (1..segments.len())
+        .rev()
+        .find(|i| match (segments[i - 1], segments[*i]) {
+            (Segment::Map { key }, Segment::Seq { .. }) => lenient_arrays.contains(&key.as_str()),
+            _ => false,
+        })?;
+
+    let mut array = value;
+    let mut skipped = String::new();
+    for segment in &segments[..pos] {
+        array = match segment {
+            Segment::Map { key } => {
+                if !skipped.is_empty() {
+                    skipped.push('.');
+                }
+                skipped.push_str(key);
+                array.get_mut(key.as_str())?
+            }
+            Segment::Seq { index } => {
+                skipped.push_str(&format!("[{}]", index));
+                array.get_mut(*index)?
+            }
+            _ => return None,
+        };
+    }
+    let index = match segments[pos] {
+        Segment::Seq { index } => *index,
+        _ => return None,
+    };
+    let elements = array.as_array_mut().filter(|x| index < x.len())?;
+    elements.remove(index);
+    skipped.push_str(&format!("[{}]", index));
+
+    Some(skipped)
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Response {
+        devices: Vec<Device>,
+        #[serde(skip)]
+        warnings: Vec<Warning>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Device {
+        id: String,
+        modules: Vec<Module>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Module {
+        #[allow(dead_code)]
+        battery_vp: u64,
+    }
+
+    impl ApiResponse for Response {
+        const LENIENT_ARRAYS: &'static [&'static str] = &["devices", "modules"];
+
+        fn set_warnings(&mut self, warnings: Vec<Warning>) {
+            self.warnings = warnings;
+        }
+    }
+
+    const JSON: &str = r#"{"devices": [
+  {"id": "a", "modules": [{"battery_vp": 5000}, {}]},
+  {"modules": []},
+  {"id": "c", "modules": []}
+]}"#;
+
+    #[test]
+    fn skip_malformed_elements() {
+        let res: Response = deserialize_lenient(JSON).expect("Failed to deserialize");
+
+        assert_that(&res.devices.iter().map(|x| x.id.as_str()).collect::<Vec<_>>()).is_equal_to(vec!["a", "c"]);
+        assert_that(&res.devices[0].modules).has_length(1);
+        assert_that(&res.warnings.iter().map(|x| x.skipped.as_str()).collect::<Vec<_>>())
+            .is_equal_to(vec!["devices[0].modules[1]", "devices[1]"]);
+        assert_that(&res.warnings[1].path).is_equal_to("devices[1]".to_string());
+    }
+
+    #[test]
+    fn fail_outside_of_lenient_arrays() {
+        let res = deserialize_lenient::<Response>(r#"{"devices": {}}"#);
+
+        assert_that(&res.map_err(|e| e.path().to_string())).is_err_containing("devices".to_string());
+    }
+
+    #[test]
+    fn strict_fails_on_first_malformed_element() {
+        let res = deserialize::<Response>(JSON);
+
+        assert_that(&res.map_err(|e| e.path().to_string())).is_err_containing("devices[0].modules[1]".to_string());
+    }
+}
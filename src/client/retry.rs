@@ -0,0 +1,213 @@
+use crate::errors::ErrorKind;
+
+use std::{
+    cmp,
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    time::{Duration, Instant},
+};
+
+/// Decides which failed API calls get retried and how long to wait in between
+///
+/// Attempt `n` waits between half and all of `base_delay * 2^(n-1)`, capped at `max_delay`. Calls that change
+/// state, e.g., `set_room_thermpoint`, are only retried if `retry_writes` is enabled.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    retryable: fn(&ErrorKind) -> bool,
+    retry_writes: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retryable: is_transient,
+            retry_writes: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Fails on the first error; this is what clients do unless configured otherwise
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Sets the number of attempts including the first one
+    pub fn max_attempts(self, max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: cmp::max(max_attempts, 1),
+            ..self
+        }
+    }
+
+    pub fn base_delay(self, base_delay: Duration) -> Self {
+        RetryPolicy { base_delay, ..self }
+    }
+
+    pub fn max_delay(self, max_delay: Duration) -> Self {
+        RetryPolicy { max_delay, ..self }
+    }
+
+    /// Replaces `is_transient` to decide which errors are worth another attempt
+    pub fn retryable(self, retryable: fn(&ErrorKind) -> bool) -> Self {
+        RetryPolicy { retryable, ..self }
+    }
+
+    /// Retries calls that change state, too; only safe if repeating such a call does no harm
+    pub fn retry_writes(self, retry_writes: bool) -> Self {
+        RetryPolicy { retry_writes, ..self }
+    }
+
+    /// Checks if a call that failed with `kind` in attempt `attempt` (starting at 1) gets another attempt
+    pub(crate) fn should_retry(&self, kind: &ErrorKind, attempt: u32, write: bool) -> bool {
+        attempt < self.max_attempts && (!write || self.retry_writes) && (self.retryable)(kind)
+    }
+
+    /// Returns the randomized delay before the attempt following attempt `attempt`
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = cmp::min(self.base_delay.saturating_mul(factor), self.max_delay);
+        let jitter = RandomState::new().hash_one(Instant::now()) as f64 / u64::MAX as f64;
+
+        backoff / 2 + backoff.mul_f64(jitter / 2.0)
+    }
+}
+
+/// Default predicate of `RetryPolicy` for network failures and server side errors; cf. `ErrorKind::is_retryable`
+pub fn is_transient(kind: &ErrorKind) -> bool {
+    kind.is_retryable()
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod should_retry {
+        use super::*;
+
+        const UNAVAILABLE: ErrorKind = ErrorKind::UnknownApiCallFailure {
+            name: "get_measure",
+            status_code: 503,
+        };
+
+        #[test]
+        fn retry_transient_errors_until_max_attempts() {
+            let policy = RetryPolicy::new().max_attempts(3);
+
+            assert_that(&policy.should_retry(&UNAVAILABLE, 2, false)).is_true();
+            assert_that(&policy.should_retry(&UNAVAILABLE, 3, false)).is_false();
+            assert_that(&policy.should_retry(&ErrorKind::AuthenticationFailed, 1, false)).is_false();
+        }
+
+        #[test]
+        fn retry_writes_only_if_enabled() {
+            let policy = RetryPolicy::new();
+
+            assert_that(&policy.should_retry(&UNAVAILABLE, 1, true)).is_false();
+            assert_that(&policy.retry_writes(true).should_retry(&UNAVAILABLE, 1, true)).is_true();
+        }
+    }
+
+    mod delay {
+        use super::*;
+
+        #[test]
+        fn grows_exponentially_up_to_max_delay() {
+            let policy = RetryPolicy::new()
+                .base_delay(Duration::from_millis(100))
+                .max_delay(Duration::from_millis(1000));
+
+            assert_that(&policy.delay(1)).is_greater_than_or_equal_to(Duration::from_millis(50));
+            assert_that(&policy.delay(1)).is_less_than_or_equal_to(Duration::from_millis(100));
+            assert_that(&policy.delay(3)).is_greater_than_or_equal_to(Duration::from_millis(200));
+            assert_that(&policy.delay(3)).is_less_than_or_equal_to(Duration::from_millis(400));
+            assert_that(&policy.delay(40)).is_less_than_or_equal_to(Duration::from_millis(1000));
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod authenticated_client {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{now, set_room_thermpoint, token, STATION_DATA},
+            AuthenticatedClient, Netatmo, NetatmoClient,
+        };
+        use std::time::Duration;
+
+        const UNAVAILABLE: &str = "Service Unavailable";
+
+        fn client(server: &FakeServer, retry_policy: RetryPolicy) -> AuthenticatedClient {
+            NetatmoClient::builder()
+                .base_url(server.url())
+                .retry_policy(retry_policy.base_delay(Duration::from_millis(1)))
+                .build_with_token(token(now()))
+                .expect("Failed to build client")
+        }
+
+        #[test]
+        fn retry_transient_failure() {
+            let server = FakeServer::start(vec![(503, UNAVAILABLE), (200, STATION_DATA)]);
+            let client = client(&server, RetryPolicy::new());
+
+            let res = client.get_station_data("device");
+
+            assert_that(&res).is_ok();
+            assert_that(&server.requests()).has_length(2);
+        }
+
+        #[test]
+        fn retry_internal_server_error_with_any_code() {
+            let server = FakeServer::start(vec![
+                (500, r#"{"error": {"code": 21, "message": "Invalid argument"}}"#),
+                (200, STATION_DATA),
+            ]);
+            let client = client(&server, RetryPolicy::new());
+
+            let res = client.get_station_data("device");
+
+            assert_that(&res).is_ok();
+            assert_that(&server.requests()).has_length(2);
+        }
+
+        #[test]
+        fn give_up_after_max_attempts() {
+            let server = FakeServer::start(vec![(503, UNAVAILABLE), (503, UNAVAILABLE)]);
+            let client = client(&server, RetryPolicy::new().max_attempts(2));
+
+            let res = client.get_station_data("device");
+
+            assert_that(&res.map_err(|e| e.kind().clone())).is_err_containing(ErrorKind::UnknownApiCallFailure {
+                name: "get_station_data",
+                status_code: 503,
+            });
+            assert_that(&server.requests()).has_length(2);
+        }
+
+        #[test]
+        fn do_not_retry_writes_by_default() {
+            let server = FakeServer::start(vec![(503, UNAVAILABLE)]);
+            let client = client(&server, RetryPolicy::new());
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_err();
+            assert_that(&server.requests()).has_length(1);
+        }
+    }
+}
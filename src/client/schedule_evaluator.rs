@@ -0,0 +1,501 @@
+use crate::{
+    client::{
+        get_home_status,
+        get_homes_data::{self, ThermSchedule, Zone},
+        weekly_schedule::Weekday,
+    },
+    errors::{Error, ErrorKind, Result},
+};
+
+use chrono::{DateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+/// 1970-01-01 has been a Thursday, i.e., three days after the start of its week
+const EPOCH_WEEK_OFFSET: i64 = 3 * 24 * 60 * 60;
+
+/// Temperature a room should have and what determines it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setpoint {
+    pub temp: f64,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Zone of the timetable
+    Schedule { zone_id: i64 },
+    /// `away_temp` of the schedule
+    Away,
+    /// `hg_temp` of the schedule
+    FrostGuard,
+    /// Temporary setpoint of the room like `manual`, `max` or `off`
+    Override { mode: String },
+}
+
+/// Setpoint of a room taking effect at `time`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub time: i64,
+    pub setpoint: Setpoint,
+}
+
+/// Answers which temperature a room should have according to a home's schedule, mode and temporary setpoints
+///
+/// Timestamps are mapped onto the timetable in the local time of the home's timezone, e.g., `Europe/Berlin`, so the
+/// UTC offset follows daylight saving time. Homes without a timezone use UTC unless `utc_offset` sets a fixed offset.
+pub struct ScheduleEvaluator<'a> {
+    schedule: &'a ThermSchedule,
+    therm_mode: &'a str,
+    status: Option<&'a get_home_status::Home>,
+    timezone: Option<Tz>,
+    utc_offset: Option<i64>,
+}
+
+impl<'a> ScheduleEvaluator<'a> {
+    /// Evaluates the selected schedule of `home`; fails if the timezone of `home` is not in the tz database
+    pub fn new(home: &'a get_homes_data::Home) -> Result<Self> {
+        let schedule = home
+            .therm_schedules
+            .iter()
+            .find(|x| x.selected)
+            .ok_or_else(|| invalid_schedule(format!("home '{}' has no selected schedule", home.id)))?;
+        let timezone = match home.timezone.as_str() {
+            "" => None,
+            name => Some(
+                name.parse::<Tz>()
+                    .map_err(|_| invalid_schedule(format!("home '{}' has unknown timezone '{}'", home.id, name)))?,
+            ),
+        };
+
+        Ok(ScheduleEvaluator {
+            schedule,
+            therm_mode: &home.therm_mode,
+            status: None,
+            timezone,
+            utc_offset: None,
+        })
+    }
+
+    /// Evaluates `schedule` instead of the selected one, e.g., an edited copy
+    pub fn schedule(self, schedule: &'a ThermSchedule) -> Self {
+        ScheduleEvaluator { schedule, ..self }
+    }
+
+    /// Applies the setpoint modes and end times of rooms in `status` instead of the mode of the home
+    pub fn home_status(self, status: &'a get_home_status::Home) -> Self {
+        ScheduleEvaluator {
+            status: Some(status),
+            ..self
+        }
+    }
+
+    /// Overrides the timezone of the home by a fixed offset of its local time from UTC in seconds
+    pub fn utc_offset(self, utc_offset: i64) -> Self {
+        ScheduleEvaluator {
+            utc_offset: Some(utc_offset),
+            ..self
+        }
+    }
+
+    /// Setpoint of `room_id` at `hour:minute` on `day` according to the timetable alone
+    pub fn scheduled(&self, room_id: &str, day: Weekday, hour: u8, minute: u8) -> Result<Setpoint> {
+        let m_offset = day as i64 * 24 * 60 + i64::from(hour) * 60 + i64::from(minute);
+        self.scheduled_at_offset(room_id, m_offset)
+    }
+
+    /// Setpoint of `room_id` at Unix timestamp `time` including the home's mode and temporary setpoints
+    pub fn setpoint(&self, room_id: &str, time: i64) -> Result<Setpoint> {
+        let schedule = self.schedule;
+        let mode = match self.status {
+            Some(status) => {
+                let room = status
+                    .rooms
+                    .iter()
+                    .find(|x| x.id == room_id)
+                    .ok_or_else(|| invalid_schedule(format!("room '{}' has no status", room_id)))?;
+                let active = room.therm_setpoint_start_time <= time
+                    && (room.therm_setpoint_end_time == 0 || time < room.therm_setpoint_end_time);
+                match room.therm_setpoint_mode.as_str() {
+                    "manual" | "max" | "off" if active => {
+                        return Ok(Setpoint {
+                            temp: room.therm_setpoint_temperature,
+                            source: Source::Override {
+                                mode: room.therm_setpoint_mode.clone(),
+                            },
+                        })
+                    }
+                    mode @ "away" | mode @ "hg" if active => mode,
+                    _ => "schedule",
+                }
+            }
+            None => self.therm_mode,
+        };
+
+        match mode {
+            "away" => Ok(Setpoint {
+                temp: schedule.away_temp as f64,
+                source: Source::Away,
+            }),
+            "hg" => Ok(Setpoint {
+                temp: schedule.hg_temp as f64,
+                source: Source::FrostGuard,
+            }),
+            _ => {
+                let week_time = (time + self.offset_at(time) + EPOCH_WEEK_OFFSET).rem_euclid(SECONDS_PER_WEEK);
+                self.scheduled_at_offset(room_id, week_time / 60)
+            }
+        }
+    }
+
+    /// Changes of the setpoint of `room_id` within `hours` after `time`
+    pub fn upcoming_changes(&self, room_id: &str, time: i64, hours: u32) -> Result<Vec<Change>> {
+        let end = time + i64::from(hours) * 60 * 60;
+        let offset_changes = self.offset_changes(time, end);
+        let mut offsets: Vec<i64> = std::iter::once(time)
+            .chain(offset_changes.iter().cloned())
+            .map(|x| self.offset_at(x))
+            .collect();
+        offsets.sort_unstable();
+        offsets.dedup();
+        // Start of the week containing `time` in local time; switches map to UTC with every offset of the period and
+        // `setpoint` sorts out the candidates that do not change anything.
+        let local_time = time + self.offset_at(time);
+        let week_start = local_time - (local_time + EPOCH_WEEK_OFFSET).rem_euclid(SECONDS_PER_WEEK);
+        let mut candidates: Vec<i64> = (0..=(end - time) / SECONDS_PER_WEEK + 1)
+            .flat_map(|week| {
+                self.schedule
+                    .timetable
+                    .iter()
+                    .map(move |x| week_start + week * SECONDS_PER_WEEK + x.m_offset * 60)
+            })
+            .flat_map(|local| offsets.iter().map(move |offset| local - offset))
+            .collect();
+        // Switches skipped by a clock change take effect with the change
+        candidates.extend(offset_changes);
+        if let Some(status) = self.status {
+            candidates.extend(
+                status
+                    .rooms
+                    .iter()
+                    .filter(|x| x.id == room_id)
+                    .flat_map(|x| vec![x.therm_setpoint_start_time, x.therm_setpoint_end_time]),
+            );
+        }
+        candidates.retain(|x| *x > time && *x <= end);
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut current = self.setpoint(room_id, time)?;
+        let mut changes = Vec::new();
+        for candidate in candidates {
+            let setpoint = self.setpoint(room_id, candidate)?;
+            if setpoint != current {
+                current = setpoint.clone();
+                changes.push(Change {
+                    time: candidate,
+                    setpoint,
+                });
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Offset of the home's local time from UTC in seconds at Unix timestamp `time`
+    fn offset_at(&self, time: i64) -> i64 {
+        match (self.utc_offset, self.timezone) {
+            (Some(utc_offset), _) => utc_offset,
+            (None, Some(timezone)) => DateTime::from_timestamp(time, 0)
+                .map(|x| {
+                    timezone
+                        .offset_from_utc_datetime(&x.naive_utc())
+                        .fix()
+                        .local_minus_utc()
+                })
+                .map(i64::from)
+                .unwrap_or(0),
+            (None, None) => 0,
+        }
+    }
+
+    /// Timestamps within `(start, end]` at which the UTC offset changes, assuming at most one change per day
+    fn offset_changes(&self, start: i64, end: i64) -> Vec<i64> {
+        let mut changes = Vec::new();
+        let mut from = start;
+        while from < end {
+            let to = (from + SECONDS_PER_DAY).min(end);
+            let offset = self.offset_at(from);
+            if self.offset_at(to) != offset {
+                let (mut before, mut after) = (from, to);
+                while after - before > 1 {
+                    let mid = before + (after - before) / 2;
+                    if self.offset_at(mid) == offset {
+                        before = mid;
+                    } else {
+                        after = mid;
+                    }
+                }
+                changes.push(after);
+            }
+            from = to;
+        }
+
+        changes
+    }
+
+    fn scheduled_at_offset(&self, room_id: &str, m_offset: i64) -> Result<Setpoint> {
+        let timetable = &self.schedule.timetable;
+        // Before the first switch of the week, the last one of the previous week still applies
+        let entry = timetable
+            .iter()
+            .filter(|x| x.m_offset <= m_offset)
+            .max_by_key(|x| x.m_offset)
+            .or_else(|| timetable.iter().max_by_key(|x| x.m_offset))
+            .ok_or_else(|| invalid_schedule(format!("schedule '{}' has an empty timetable", self.schedule.id)))?;
+        let zone = self
+            .schedule
+            .zones
+            .iter()
+            .find(|x| x.id == entry.zone_id)
+            .ok_or_else(|| invalid_schedule(format!("zone {} does not exist", entry.zone_id)))?;
+        let temp = zone_temp(zone, room_id).ok_or_else(|| {
+            invalid_schedule(format!(
+                "zone '{}' has no temperature for room '{}'",
+                zone.name, room_id
+            ))
+        })?;
+
+        Ok(Setpoint {
+            temp,
+            source: Source::Schedule { zone_id: zone.id },
+        })
+    }
+}
+
+fn zone_temp(zone: &Zone, room_id: &str) -> Option<f64> {
+    zone.rooms_temp
+        .iter()
+        .find(|x| x.room_id == room_id)
+        .map(|x| x.temp)
+        .or_else(|| {
+            zone.rooms
+                .iter()
+                .flatten()
+                .find(|x| x.id == room_id)
+                .map(|x| x.therm_setpoint_temperature)
+        })
+}
+
+fn invalid_schedule(msg: String) -> Error {
+    Error::from(ErrorKind::InvalidSchedule { msg })
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::{
+        get_homes_data::{RoomsTemp, Timetable},
+        weekly_schedule::WeeklySchedule,
+    };
+
+    /// Tuesday, 2020-01-07 14:10 UTC
+    const TUESDAY_14_10: i64 = 1_578_406_200;
+
+    fn home() -> get_homes_data::Home {
+        let zone = |id: i64, name: &str, temp: f64| Zone {
+            id,
+            name: name.to_string(),
+            rooms_temp: vec![RoomsTemp {
+                room_id: "living".to_string(),
+                temp,
+                ..RoomsTemp::default()
+            }],
+            ..Zone::default()
+        };
+        let zones = vec![zone(0, "Comfort", 21.0), zone(1, "Night", 17.0), zone(4, "Eco", 18.5)];
+        let mut schedule = ThermSchedule {
+            id: "schedule".to_string(),
+            away_temp: 12,
+            hg_temp: 7,
+            selected: true,
+            ..ThermSchedule::default()
+        };
+        WeeklySchedule::parse(zones, "Mon-Fri 06:30 Comfort, 08:00 Eco, 17:00 Comfort, 22:00 Night")
+            .and_then(|x| x.apply_to(&mut schedule))
+            .expect("Failed to compile schedule");
+
+        get_homes_data::Home {
+            id: "home".to_string(),
+            therm_mode: "schedule".to_string(),
+            therm_schedules: vec![schedule],
+            ..get_homes_data::Home::default()
+        }
+    }
+
+    fn status(mode: &str, end_time: i64) -> get_home_status::Home {
+        get_home_status::Home {
+            rooms: vec![get_home_status::Room {
+                id: "living".to_string(),
+                therm_setpoint_mode: mode.to_string(),
+                therm_setpoint_temperature: 23.0,
+                therm_setpoint_start_time: TUESDAY_14_10 - 600,
+                therm_setpoint_end_time: end_time,
+                ..get_home_status::Room::default()
+            }],
+            ..get_home_status::Home::default()
+        }
+    }
+
+    mod setpoint {
+        use super::*;
+
+        #[test]
+        fn follow_timetable_in_local_time() {
+            let home = home();
+            let evaluator = ScheduleEvaluator::new(&home).unwrap();
+
+            let scheduled = evaluator.scheduled("living", Weekday::Tue, 14, 10);
+            let at_local_time = evaluator.utc_offset(3 * 3600).setpoint("living", TUESDAY_14_10);
+
+            assert_that(&scheduled.map(|x| x.temp)).is_ok().is_equal_to(18.5);
+            assert_that(&at_local_time.map(|x| x.temp)).is_ok().is_equal_to(21.0);
+        }
+
+        #[test]
+        fn wrap_around_week() {
+            let mut home = home();
+            home.therm_schedules[0].timetable.retain(|x| x.m_offset != 0);
+            let evaluator = ScheduleEvaluator::new(&home).unwrap();
+
+            let setpoint = evaluator.scheduled("living", Weekday::Mon, 3, 0);
+
+            assert_that(&setpoint.map(|x| x.source))
+                .is_ok()
+                .is_equal_to(Source::Schedule { zone_id: 1 });
+        }
+
+        #[test]
+        fn apply_home_mode_and_overrides() {
+            let mut home = home();
+            home.therm_mode = "away".to_string();
+            let status = status("manual", TUESDAY_14_10 + 3600);
+
+            let away = ScheduleEvaluator::new(&home).unwrap().setpoint("living", TUESDAY_14_10);
+            let manual = ScheduleEvaluator::new(&home)
+                .unwrap()
+                .home_status(&status)
+                .setpoint("living", TUESDAY_14_10);
+
+            assert_that(&away.map(|x| x.temp)).is_ok().is_equal_to(12.0);
+            assert_that(&manual).is_ok().is_equal_to(Setpoint {
+                temp: 23.0,
+                source: Source::Override {
+                    mode: "manual".to_string(),
+                },
+            });
+        }
+    }
+
+    mod daylight_saving_time {
+        use super::*;
+
+        /// Tuesdays 2020-03-24 and 2020-03-31 05:15 UTC; Berlin switched from CET to CEST in between
+        const TUESDAY_BEFORE_DST: i64 = 1_585_026_900;
+        const TUESDAY_AFTER_DST: i64 = 1_585_631_700;
+        /// 2020-03-29 01:00 UTC, when clocks in Berlin jumped from 02:00 to 03:00
+        const DST_START: i64 = 1_585_443_600;
+
+        fn berlin_home() -> get_homes_data::Home {
+            get_homes_data::Home {
+                timezone: "Europe/Berlin".to_string(),
+                ..home()
+            }
+        }
+
+        #[test]
+        fn follow_offset_of_home_timezone() {
+            let home = berlin_home();
+            let evaluator = ScheduleEvaluator::new(&home).unwrap();
+
+            let before = evaluator.setpoint("living", TUESDAY_BEFORE_DST);
+            let after = evaluator.setpoint("living", TUESDAY_AFTER_DST);
+            let fixed = evaluator.utc_offset(3600).setpoint("living", TUESDAY_AFTER_DST);
+
+            assert_that(&before.map(|x| x.temp)).is_ok().is_equal_to(17.0);
+            assert_that(&after.map(|x| x.temp)).is_ok().is_equal_to(21.0);
+            assert_that(&fixed.map(|x| x.temp)).is_ok().is_equal_to(17.0);
+        }
+
+        #[test]
+        fn list_switches_across_dst_change() {
+            let mut home = berlin_home();
+            // Sunday 02:30 does not exist on the day of the change
+            home.therm_schedules[0].timetable.push(Timetable {
+                zone_id: 0,
+                m_offset: 6 * 24 * 60 + 150,
+                ..Timetable::default()
+            });
+            home.therm_schedules[0].timetable.push(Timetable {
+                zone_id: 1,
+                m_offset: 6 * 24 * 60 + 600,
+                ..Timetable::default()
+            });
+            let evaluator = ScheduleEvaluator::new(&home).unwrap();
+
+            let changes = evaluator.upcoming_changes("living", DST_START - 3 * 3600, 36);
+
+            assert_that(&changes.map(|x| x.into_iter().map(|c| (c.time, c.setpoint.temp)).collect::<Vec<_>>()))
+                .is_ok()
+                .is_equal_to(vec![
+                    (DST_START, 21.0),
+                    (DST_START + 7 * 3600, 17.0),
+                    (DST_START + 27 * 3600 + 1800, 21.0),
+                    (DST_START + 29 * 3600, 18.5),
+                ]);
+        }
+
+        #[test]
+        fn reject_unknown_timezone() {
+            let home = get_homes_data::Home {
+                timezone: "Mars/Olympus_Mons".to_string(),
+                ..home()
+            };
+
+            assert_that(&ScheduleEvaluator::new(&home).map(|_| ())).is_err();
+        }
+    }
+
+    mod upcoming_changes {
+        use super::*;
+
+        #[test]
+        fn list_override_end_and_switches() {
+            let home = home();
+            let status = status("manual", TUESDAY_14_10 + 3600);
+            let evaluator = ScheduleEvaluator::new(&home).unwrap().home_status(&status);
+
+            let changes = evaluator.upcoming_changes("living", TUESDAY_14_10, 10);
+
+            assert_that(&changes.map(|x| x.into_iter().map(|c| (c.time, c.setpoint.temp)).collect::<Vec<_>>()))
+                .is_ok()
+                .is_equal_to(vec![
+                    (TUESDAY_14_10 + 3600, 18.5),
+                    (TUESDAY_14_10 + 2 * 3600 + 50 * 60, 21.0),
+                    (TUESDAY_14_10 + 7 * 3600 + 50 * 60, 17.0),
+                ]);
+        }
+
+        #[test]
+        fn fail_for_rooms_without_temperature() {
+            let mut home = home();
+            home.therm_schedules[0].timetable = vec![Timetable::default()];
+            let evaluator = ScheduleEvaluator::new(&home).unwrap();
+
+            assert_that(&evaluator.upcoming_changes("kitchen", TUESDAY_14_10, 24)).is_err();
+        }
+    }
+}
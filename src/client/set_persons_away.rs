@@ -0,0 +1,149 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{get_home_data::PersonId, response::ApiResponse},
+    errors::{Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    person_id: Option<&'a PersonId>,
+}
+
+impl<'a> Parameters<'a> {
+    /// Marks all persons of the home as away, i.e., the home as empty
+    pub fn new(home_id: &'a str) -> Self {
+        Parameters {
+            home_id,
+            person_id: None,
+        }
+    }
+
+    /// Marks only this person as away
+    pub fn person_id(self, person_id: &'a PersonId) -> Self {
+        Parameters {
+            person_id: Some(person_id),
+            ..self
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.person_id {
+            Some(person_id) if person_id.home_id() != self.home_id => Err(Error::from(ErrorKind::InvalidParameters {
+                name: "set_persons_away",
+                msg: format!("person '{}' does not belong to home '{}'", person_id, self.home_id),
+            })),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        map.insert("home_id", p.home_id.to_string());
+        if let Some(person_id) = p.person_id {
+            map.insert("person_id", person_id.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#setpersonsaway
+#[cfg(feature = "blocking")]
+pub(crate) fn set_persons_away(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("set_persons_away", "api/setpersonsaway", &params)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn set_persons_away_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    parameters.validate()?;
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client
+        .call_write("set_persons_away", "api/setpersonsaway", &params)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::get_home_data::{Home, Person};
+
+    fn person_id(home_id: &str) -> PersonId {
+        let home = Home {
+            id: home_id.to_string(),
+            persons: vec![Person {
+                id: "91827364-5555-4444-3333-222211110000".to_string(),
+                ..Person::default()
+            }],
+            ..Home::default()
+        };
+
+        home.person_id("91827364-5555-4444-3333-222211110000").unwrap()
+    }
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn mark_whole_home_as_empty() {
+            let parameters = Parameters::new("home");
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&params.len()).is_equal_to(1);
+            assert_that(&params["home_id"]).is_equal_to("home".to_string());
+        }
+
+        #[test]
+        fn mark_single_person_as_away() {
+            let person_id = person_id("home");
+            let parameters = Parameters::new("home").person_id(&person_id);
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&params["person_id"]).is_equal_to("91827364-5555-4444-3333-222211110000".to_string());
+        }
+
+        #[test]
+        fn reject_persons_of_other_homes() {
+            let person_id = person_id("other");
+            let parameters = Parameters::new("home").person_id(&person_id);
+
+            let res = parameters.validate().map_err(|e| e.kind().clone());
+
+            assert_that(&res).is_err_containing(ErrorKind::InvalidParameters {
+                name: "set_persons_away",
+                msg: "person '91827364-5555-4444-3333-222211110000' does not belong to home 'home'".to_string(),
+            });
+        }
+    }
+}
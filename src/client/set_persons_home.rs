@@ -0,0 +1,133 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{get_home_data::PersonId, response::ApiResponse},
+    errors::{Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    person_ids: &'a [PersonId],
+}
+
+impl<'a> Parameters<'a> {
+    /// Marks these persons as at home; they must belong to `home_id`
+    pub fn new(home_id: &'a str, person_ids: &'a [PersonId]) -> Self {
+        Parameters { home_id, person_ids }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let msg = if self.person_ids.is_empty() {
+            "no persons given".to_string()
+        } else if let Some(person_id) = self.person_ids.iter().find(|x| x.home_id() != self.home_id) {
+            format!("person '{}' does not belong to home '{}'", person_id, self.home_id)
+        } else {
+            return Ok(());
+        };
+
+        Err(Error::from(ErrorKind::InvalidParameters {
+            name: "set_persons_home",
+            msg,
+        }))
+    }
+}
+
+impl<'a> From<&'a Parameters<'a>> for Value {
+    fn from(p: &'a Parameters) -> Value {
+        let person_ids: Vec<&str> = p.person_ids.iter().map(PersonId::as_str).collect();
+        json!({
+            "home_id": p.home_id,
+            "person_ids": person_ids,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/security#setpersonshome
+#[cfg(feature = "blocking")]
+pub(crate) fn set_persons_home(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client.call_write_json("set_persons_home", "api/setpersonshome", &body)
+}
+
+#[cfg(feature = "async")]
+pub(crate) async fn set_persons_home_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client
+        .call_write_json("set_persons_home", "api/setpersonshome", &body)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::get_home_data::{Home, Person};
+
+    fn home(id: &str) -> Home {
+        Home {
+            id: id.to_string(),
+            persons: vec![Person {
+                id: "91827364-5555-4444-3333-222211110000".to_string(),
+                ..Person::default()
+            }],
+            ..Home::default()
+        }
+    }
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn encode_person_ids_as_array() {
+            let person_ids = home("home").person_ids();
+            let parameters = Parameters::new("home", &person_ids);
+
+            let body: Value = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&body).is_equal_to(json!({
+                "home_id": "home",
+                "person_ids": ["91827364-5555-4444-3333-222211110000"],
+            }));
+        }
+
+        #[test]
+        fn reject_persons_of_other_homes() {
+            let person_ids = home("other").person_ids();
+            let parameters = Parameters::new("home", &person_ids);
+
+            let res = parameters.validate().map_err(|e| e.kind().clone());
+
+            assert_that(&res).is_err_containing(ErrorKind::InvalidParameters {
+                name: "set_persons_home",
+                msg: "person '91827364-5555-4444-3333-222211110000' does not belong to home 'home'".to_string(),
+            });
+        }
+
+        #[test]
+        fn reject_unknown_persons() {
+            assert_that(&home("home").person_id("unknown")).is_none();
+        }
+    }
+}
@@ -1,54 +1,114 @@
-use crate::{client::AuthenticatedClient, errors::Result};
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{authenticate::unix_timestamp, response::ApiResponse},
+    errors::{Error, ErrorKind, Result},
+};
 
 use serde::Deserialize;
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, time::Duration};
 
 pub struct Parameters<'a> {
     home_id: &'a str,
     room_id: &'a str,
     mode: Mode,
-    temp: Option<f32>,
     endtime: Option<usize>,
 }
 
+/// Setpoint mode of a room along with the values each mode requires
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mode {
-    Manual,
+    /// Holds `temp` °C until `Parameters::date_end` or the next change of the schedule
+    Manual { temp: f32 },
+    /// Heats at full power for `duration`
+    Max { duration: Duration },
+    /// Stops heating until `Parameters::date_end` or further notice
+    Off,
+    /// Returns to the mode of the home
     Home,
 }
+
 impl fmt::Display for Mode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
-            Mode::Manual => "manual",
+            Mode::Manual { .. } => "manual",
+            Mode::Max { .. } => "max",
+            Mode::Off => "off",
             Mode::Home => "home",
         };
         write!(f, "{}", s)
     }
 }
 
+impl Mode {
+    /// Describes why this mode cannot be sent with `endtime`
+    pub(crate) fn check(self, endtime: Option<usize>) -> std::result::Result<(), String> {
+        let msg = match (self, endtime) {
+            (Mode::Manual { temp }, _) if !temp.is_finite() => format!("invalid temperature {}", temp),
+            (Mode::Max { duration }, _) if duration.as_secs() == 0 => "max mode requires a duration".to_string(),
+            (Mode::Max { .. }, Some(_)) => "max mode ends after its duration, not at a date".to_string(),
+            (Mode::Home, Some(_)) => "home mode cannot end at a date".to_string(),
+            _ => return Ok(()),
+        };
+
+        Err(msg)
+    }
+
+    pub(crate) fn temp(self) -> Option<f32> {
+        match self {
+            Mode::Manual { temp } => Some(temp),
+            _ => None,
+        }
+    }
+
+    /// Unix timestamp when the setpoint ends if it is sent at `now`
+    pub(crate) fn endtime(self, endtime: Option<usize>, now: usize) -> Option<usize> {
+        match self {
+            // The API expects an end date, so the duration starts when the request is sent
+            Mode::Max { duration } => Some(now + duration.as_secs() as usize),
+            _ => endtime,
+        }
+    }
+}
+
 impl<'a> Parameters<'a> {
     pub fn new(home_id: &'a str, room_id: &'a str, mode: Mode) -> Self {
         Parameters {
             home_id,
             room_id,
             mode,
-            temp: None,
             endtime: None,
         }
     }
 
-    pub fn temp(self, temp: f32) -> Self {
+    /// Unix timestamp when the setpoint ends; applies to `Mode::Manual` and `Mode::Off`
+    pub fn date_end(self, date_end: usize) -> Self {
         Parameters {
-            temp: Some(temp),
+            endtime: Some(date_end),
             ..self
         }
     }
 
-    pub fn date_end(self, date_end: usize) -> Self {
+    /// Fixes the end of the setpoint for a request sent at `now`
+    fn sent_at(&self, now: usize) -> Parameters<'a> {
         Parameters {
-            endtime: Some(date_end),
-            ..self
+            home_id: self.home_id,
+            room_id: self.room_id,
+            mode: self.mode,
+            endtime: self.mode.endtime(self.endtime, now),
         }
     }
+
+    fn validate(&self) -> Result<()> {
+        self.mode.check(self.endtime).map_err(|msg| {
+            Error::from(ErrorKind::InvalidParameters {
+                name: "set_room_thermpoint",
+                msg,
+            })
+        })
+    }
 }
 
 pub enum Type {
@@ -75,7 +135,7 @@ impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
         map.insert("home_id", p.home_id.to_string());
         map.insert("room_id", p.room_id.to_string());
         map.insert("mode", p.mode.to_string());
-        if let Some(temp) = p.temp {
+        if let Some(temp) = p.mode.temp() {
             map.insert("temp", temp.to_string());
         }
         if let Some(endtime) = p.endtime {
@@ -92,14 +152,98 @@ pub struct Response {
     pub time_server: usize,
 }
 
+impl ApiResponse for Response {}
+
 // cf. https://dev.netatmo.com/resources/technical/reference/energy/setroomthermpoint
+#[cfg(feature = "blocking")]
 pub fn set_room_thermpoint(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
-    let params: HashMap<&str, String> = parameters.into();
-    let mut params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
-
-    client.call(
-        "set_room_thermpoint",
-        "https://api.netatmo.com/api/setroomthermpoint",
-        &mut params,
-    )
+    parameters.validate()?;
+    let parameters = parameters.sent_at(unix_timestamp() as usize);
+    let params: HashMap<&str, String> = (&parameters).into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("set_room_thermpoint", "api/setroomthermpoint", &params)
+}
+
+#[cfg(feature = "async")]
+pub async fn set_room_thermpoint_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    parameters.validate()?;
+    let parameters = parameters.sent_at(unix_timestamp() as usize);
+    let params: HashMap<&str, String> = (&parameters).into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client
+        .call_write("set_room_thermpoint", "api/setroomthermpoint", &params)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn send_temperature_of_manual_mode() {
+            let parameters = Parameters::new("home", "room", Mode::Manual { temp: 19.5 }).date_end(1_600_000_000);
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&params["mode"]).is_equal_to("manual".to_string());
+            assert_that(&params["temp"]).is_equal_to("19.5".to_string());
+            assert_that(&params["endtime"]).is_equal_to("1600000000".to_string());
+        }
+
+        #[test]
+        fn send_end_of_max_mode() {
+            let parameters = Parameters::new(
+                "home",
+                "room",
+                Mode::Max {
+                    duration: Duration::from_secs(1800),
+                },
+            );
+
+            let params: HashMap<&str, String> = (&parameters.sent_at(1_600_000_000)).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&params["mode"]).is_equal_to("max".to_string());
+            assert_that(&params.contains_key("temp")).is_false();
+            assert_that(&params["endtime"]).is_equal_to("1600001800".to_string());
+        }
+
+        #[test]
+        fn keep_end_date_of_manual_mode() {
+            let parameters = Parameters::new("home", "room", Mode::Manual { temp: 19.5 }).date_end(1_600_000_000);
+
+            let params: HashMap<&str, String> = (&parameters.sent_at(1_500_000_000)).into();
+
+            assert_that(&params["endtime"]).is_equal_to("1600000000".to_string());
+        }
+
+        #[test]
+        fn reject_invalid_combinations() {
+            let max = Parameters::new(
+                "home",
+                "room",
+                Mode::Max {
+                    duration: Duration::from_secs(1800),
+                },
+            )
+            .date_end(1_600_000_000);
+            let home = Parameters::new("home", "room", Mode::Home).date_end(1_600_000_000);
+            let manual = Parameters::new("home", "room", Mode::Manual { temp: f32::NAN });
+
+            assert_that(&max.validate()).is_err();
+            assert_that(&home.validate()).is_err();
+            assert_that(&manual.validate()).is_err();
+        }
+    }
 }
@@ -0,0 +1,385 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{authenticate::unix_timestamp, response::ApiResponse, set_room_thermpoint::Mode},
+    errors::{ApiErrorCode, Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Map, Number, Value};
+
+/// Desired state of rooms and modules of a home, sent in a single request
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    rooms: Vec<RoomState<'a>>,
+    modules: Vec<ModuleState<'a>>,
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(home_id: &'a str) -> Self {
+        Parameters {
+            home_id,
+            rooms: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+
+    pub fn room(mut self, room: RoomState<'a>) -> Self {
+        self.rooms.push(room);
+        self
+    }
+
+    pub fn module(mut self, module: ModuleState<'a>) -> Self {
+        self.modules.push(module);
+        self
+    }
+
+    /// Fixes the end of the room setpoints for a request sent at `now`
+    fn sent_at(&self, now: usize) -> Parameters<'a> {
+        Parameters {
+            home_id: self.home_id,
+            rooms: self.rooms.iter().map(|x| x.sent_at(now)).collect(),
+            modules: self.modules.clone(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let room_errors = self.rooms.iter().filter_map(|x| {
+            x.mode
+                .check(x.endtime)
+                .err()
+                .map(|msg| format!("room '{}': {}", x.id, msg))
+        });
+        let module_errors = self.modules.iter().filter_map(|x| {
+            let msg = if x.is_empty() {
+                "no state given"
+            } else if x.brightness.is_some_and(|x| x > 100) {
+                "brightness exceeds 100"
+            } else if x.target_position.is_some_and(|x| x > 100) {
+                "target position exceeds 100"
+            } else {
+                return None;
+            };
+            Some(format!("module '{}': {}", x.id, msg))
+        });
+        let mut errors: Vec<String> = room_errors.chain(module_errors).collect();
+        if self.rooms.is_empty() && self.modules.is_empty() {
+            errors.push("no rooms or modules given".to_string());
+        }
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::from(ErrorKind::InvalidParameters {
+            name: "set_state",
+            msg: errors.join("; "),
+        }))
+    }
+}
+
+/// Setpoint of a room
+pub struct RoomState<'a> {
+    id: &'a str,
+    mode: Mode,
+    endtime: Option<usize>,
+}
+
+impl<'a> RoomState<'a> {
+    pub fn new(id: &'a str, mode: Mode) -> Self {
+        RoomState {
+            id,
+            mode,
+            endtime: None,
+        }
+    }
+
+    /// Unix timestamp when the setpoint ends; applies to `Mode::Manual` and `Mode::Off`
+    pub fn date_end(self, date_end: usize) -> Self {
+        RoomState {
+            endtime: Some(date_end),
+            ..self
+        }
+    }
+
+    fn sent_at(&self, now: usize) -> RoomState<'a> {
+        RoomState {
+            id: self.id,
+            mode: self.mode,
+            endtime: self.mode.endtime(self.endtime, now),
+        }
+    }
+}
+
+/// State of a module like a light, a shutter or a camera; only the given properties change
+#[derive(Clone)]
+pub struct ModuleState<'a> {
+    id: &'a str,
+    bridge: Option<&'a str>,
+    on: Option<bool>,
+    brightness: Option<u8>,
+    target_position: Option<u8>,
+    monitoring: Option<bool>,
+}
+
+impl<'a> ModuleState<'a> {
+    pub fn new(id: &'a str) -> Self {
+        ModuleState {
+            id,
+            bridge: None,
+            on: None,
+            brightness: None,
+            target_position: None,
+            monitoring: None,
+        }
+    }
+
+    /// Id of the gateway the module is connected to; required for modules behind a bridge
+    pub fn bridge(self, bridge: &'a str) -> Self {
+        ModuleState {
+            bridge: Some(bridge),
+            ..self
+        }
+    }
+
+    pub fn on(self, on: bool) -> Self {
+        ModuleState { on: Some(on), ..self }
+    }
+
+    /// Brightness of a light in percent
+    pub fn brightness(self, brightness: u8) -> Self {
+        ModuleState {
+            brightness: Some(brightness),
+            ..self
+        }
+    }
+
+    /// Opening of a shutter in percent
+    pub fn target_position(self, target_position: u8) -> Self {
+        ModuleState {
+            target_position: Some(target_position),
+            ..self
+        }
+    }
+
+    /// Switches the surveillance of a camera on or off
+    pub fn monitoring(self, monitoring: bool) -> Self {
+        ModuleState {
+            monitoring: Some(monitoring),
+            ..self
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.on.is_none() && self.brightness.is_none() && self.target_position.is_none() && self.monitoring.is_none()
+    }
+}
+
+impl<'a> From<&'a RoomState<'a>> for Value {
+    fn from(r: &'a RoomState) -> Value {
+        let mut map = Map::new();
+        map.insert("id".to_string(), r.id.into());
+        map.insert("therm_setpoint_mode".to_string(), r.mode.to_string().into());
+        if let Some(temp) = r.mode.temp() {
+            // Widening the f32 directly would send, e.g., 19.1 as 19.100000381469727
+            let temp = temp.to_string().parse().ok().and_then(Number::from_f64);
+            map.insert("therm_setpoint_temperature".to_string(), temp.into());
+        }
+        if let Some(endtime) = r.endtime {
+            map.insert("therm_setpoint_end_time".to_string(), endtime.into());
+        }
+
+        Value::Object(map)
+    }
+}
+
+impl<'a> From<&'a ModuleState<'a>> for Value {
+    fn from(m: &'a ModuleState) -> Value {
+        let mut map = Map::new();
+        map.insert("id".to_string(), m.id.into());
+        if let Some(bridge) = m.bridge {
+            map.insert("bridge".to_string(), bridge.into());
+        }
+        if let Some(on) = m.on {
+            map.insert("on".to_string(), on.into());
+        }
+        if let Some(brightness) = m.brightness {
+            map.insert("brightness".to_string(), brightness.into());
+        }
+        if let Some(target_position) = m.target_position {
+            map.insert("target_position".to_string(), target_position.into());
+        }
+        if let Some(monitoring) = m.monitoring {
+            map.insert("monitoring".to_string(), if monitoring { "on" } else { "off" }.into());
+        }
+
+        Value::Object(map)
+    }
+}
+
+impl<'a> From<&'a Parameters<'a>> for Value {
+    fn from(p: &'a Parameters) -> Value {
+        let mut home = Map::new();
+        home.insert("id".to_string(), p.home_id.into());
+        if !p.rooms.is_empty() {
+            home.insert("rooms".to_string(), p.rooms.iter().map(Value::from).collect());
+        }
+        if !p.modules.is_empty() {
+            home.insert("modules".to_string(), p.modules.iter().map(Value::from).collect());
+        }
+
+        json!({ "home": home })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+    #[serde(default)]
+    pub body: Body,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Body {
+    /// Rooms and modules whose state could not be set; the others have been set
+    #[serde(default)]
+    pub errors: Vec<ItemError>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemError {
+    /// Id of the room or module
+    pub id: String,
+    pub code: ApiErrorCode,
+}
+
+impl Response {
+    pub fn errors(&self) -> &[ItemError] {
+        &self.body.errors
+    }
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/control#setstate
+#[cfg(feature = "blocking")]
+pub fn set_state(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = (&parameters.sent_at(unix_timestamp() as usize)).into();
+
+    client.call_write_json("set_state", "api/setstate", &body)
+}
+
+#[cfg(feature = "async")]
+pub async fn set_state_async(client: &AsyncAuthenticatedClient, parameters: &Parameters<'_>) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = (&parameters.sent_at(unix_timestamp() as usize)).into();
+
+    client.call_write_json("set_state", "api/setstate", &body).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod parameters {
+        use super::*;
+        use std::time::Duration;
+
+        #[test]
+        fn build_json_body() {
+            let parameters = Parameters::new("home")
+                .room(RoomState::new("2255", Mode::Manual { temp: 19.5 }).date_end(1_600_000_000))
+                .module(ModuleState::new("shutter").bridge("gateway").target_position(0))
+                .module(ModuleState::new("camera").monitoring(false));
+
+            let body: Value = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&body).is_equal_to(json!({"home": {
+                "id": "home",
+                "rooms": [{
+                    "id": "2255",
+                    "therm_setpoint_mode": "manual",
+                    "therm_setpoint_temperature": 19.5,
+                    "therm_setpoint_end_time": 1_600_000_000
+                }],
+                "modules": [
+                    {"id": "shutter", "bridge": "gateway", "target_position": 0},
+                    {"id": "camera", "monitoring": "off"}
+                ]
+            }}));
+        }
+
+        #[test]
+        fn send_temperature_in_shortest_decimal_form() {
+            let parameters = Parameters::new("home").room(RoomState::new("2255", Mode::Manual { temp: 19.1 }));
+
+            let body: Value = (&parameters).into();
+
+            assert_that(&body["home"]["rooms"][0]["therm_setpoint_temperature"].to_string())
+                .is_equal_to("19.1".to_string());
+        }
+
+        #[test]
+        fn send_end_of_max_mode() {
+            let parameters = Parameters::new("home").room(RoomState::new(
+                "2255",
+                Mode::Max {
+                    duration: Duration::from_secs(1800),
+                },
+            ));
+
+            let body: Value = (&parameters.sent_at(1_600_000_000)).into();
+
+            assert_that(&body["home"]["rooms"][0]).is_equal_to(json!({
+                "id": "2255",
+                "therm_setpoint_mode": "max",
+                "therm_setpoint_end_time": 1_600_001_800
+            }));
+        }
+
+        #[test]
+        fn report_every_invalid_item() {
+            let parameters = Parameters::new("home")
+                .room(RoomState::new("2255", Mode::Home).date_end(1_600_000_000))
+                .module(ModuleState::new("light").brightness(120))
+                .module(ModuleState::new("plug"));
+
+            let res = parameters.validate();
+
+            assert_that(&res.map_err(|e| e.to_string()))
+                .is_err()
+                .matches(|x| x.contains("'2255'") && x.contains("'light'") && x.contains("'plug'"));
+        }
+    }
+
+    mod response {
+        use super::*;
+
+        #[test]
+        fn parse_item_errors() {
+            let body = r#"{"status": "ok", "time_server": 1580000000,
+  "body": {"errors": [{"code": 6, "id": "light"}, {"code": 42, "id": "shutter"}]}}"#;
+
+            let response: Response = serde_json::from_str(body).unwrap();
+
+            assert_that(&response.errors()).is_equal_to(
+                &[
+                    ItemError {
+                        id: "light".to_string(),
+                        code: ApiErrorCode::from(6),
+                    },
+                    ItemError {
+                        id: "shutter".to_string(),
+                        code: ApiErrorCode::Unknown(42),
+                    },
+                ][..],
+            );
+        }
+    }
+}
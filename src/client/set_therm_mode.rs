@@ -0,0 +1,188 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::response::ApiResponse,
+    errors::{Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    mode: Mode,
+    endtime: Option<usize>,
+    schedule_id: Option<&'a str>,
+}
+
+/// Heating mode of a whole home
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Follows the active weekly schedule
+    Schedule,
+    /// Heats to the schedule's away temperature until `Parameters::date_end` or further notice
+    Away,
+    /// Frost guard
+    FrostGuard,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Mode::Schedule => "schedule",
+            Mode::Away => "away",
+            Mode::FrostGuard => "hg",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl<'a> Parameters<'a> {
+    pub fn new(home_id: &'a str, mode: Mode) -> Self {
+        Parameters {
+            home_id,
+            mode,
+            endtime: None,
+            schedule_id: None,
+        }
+    }
+
+    /// Unix timestamp when the home returns to its schedule; applies to `Mode::Away` and `Mode::FrostGuard`
+    pub fn date_end(self, date_end: usize) -> Self {
+        Parameters {
+            endtime: Some(date_end),
+            ..self
+        }
+    }
+
+    /// Activates this schedule along with `Mode::Schedule`
+    pub fn schedule_id(self, schedule_id: &'a str) -> Self {
+        Parameters {
+            schedule_id: Some(schedule_id),
+            ..self
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let msg = match (self.mode, self.endtime, self.schedule_id) {
+            (Mode::Schedule, Some(_), _) => "schedule mode does not take an end date",
+            (Mode::Away, _, Some(_)) | (Mode::FrostGuard, _, Some(_)) => "only schedule mode takes a schedule id",
+            _ => return Ok(()),
+        };
+
+        Err(Error::from(ErrorKind::InvalidParameters {
+            name: "set_therm_mode",
+            msg: msg.to_string(),
+        }))
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl<'a> From<&'a Parameters<'a>> for HashMap<&str, String> {
+    fn from(p: &'a Parameters) -> HashMap<&'static str, String> {
+        let mut map = HashMap::default();
+        map.insert("home_id", p.home_id.to_string());
+        map.insert("mode", p.mode.to_string());
+        if let Some(endtime) = p.endtime {
+            map.insert("endtime", endtime.to_string());
+        }
+        if let Some(schedule_id) = p.schedule_id {
+            map.insert("schedule_id", schedule_id.to_string());
+        }
+
+        map
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/energy#setthermmode
+#[cfg(feature = "blocking")]
+pub fn set_therm_mode(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("set_therm_mode", "api/setthermmode", &params)
+}
+
+#[cfg(feature = "async")]
+pub async fn set_therm_mode_async(client: &AsyncAuthenticatedClient, parameters: &Parameters<'_>) -> Result<Response> {
+    parameters.validate()?;
+    let params: HashMap<&str, String> = parameters.into();
+    let params = params.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+
+    client.call_write("set_therm_mode", "api/setthermmode", &params).await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn frost_guard_until_date_end() {
+            let parameters = Parameters::new("home", Mode::FrostGuard).date_end(1_600_000_000);
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&params.get("mode"))
+                .is_some()
+                .is_equal_to(&"hg".to_string());
+            assert_that(&params.get("endtime"))
+                .is_some()
+                .is_equal_to(&"1600000000".to_string());
+            assert_that(&params.contains_key("schedule_id")).is_false();
+        }
+
+        #[test]
+        fn reject_date_end_for_schedule() {
+            let parameters = Parameters::new("home", Mode::Schedule).date_end(1_600_000_000);
+
+            let res = parameters.validate().map_err(|e| e.kind().clone());
+
+            assert_that(&res).is_err_containing(ErrorKind::InvalidParameters {
+                name: "set_therm_mode",
+                msg: "schedule mode does not take an end date".to_string(),
+            });
+        }
+
+        #[test]
+        fn reject_schedule_id_for_away_and_frost_guard() {
+            for mode in &[Mode::Away, Mode::FrostGuard] {
+                let parameters = Parameters::new("home", *mode).schedule_id("5a9e6c56");
+
+                let res = parameters.validate().map_err(|e| e.kind().clone());
+
+                assert_that(&res).is_err_containing(ErrorKind::InvalidParameters {
+                    name: "set_therm_mode",
+                    msg: "only schedule mode takes a schedule id".to_string(),
+                });
+            }
+        }
+
+        #[test]
+        fn send_schedule_id_for_schedule() {
+            let parameters = Parameters::new("home", Mode::Schedule).schedule_id("5a9e6c56");
+
+            let params: HashMap<&str, String> = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&params.get("schedule_id"))
+                .is_some()
+                .is_equal_to(&"5a9e6c56".to_string());
+        }
+    }
+}
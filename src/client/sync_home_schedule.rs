@@ -0,0 +1,157 @@
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+#[cfg(feature = "blocking")]
+use crate::client::AuthenticatedClient;
+use crate::{
+    client::{get_homes_data::ThermSchedule, response::ApiResponse},
+    errors::{Error, ErrorKind, Result},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub struct Parameters<'a> {
+    home_id: &'a str,
+    schedule: &'a ThermSchedule,
+}
+
+impl<'a> Parameters<'a> {
+    /// Replaces the schedule with the id of `schedule` by `schedule`, e.g., one from `get_homes_data` after editing
+    pub fn new(home_id: &'a str, schedule: &'a ThermSchedule) -> Self {
+        Parameters { home_id, schedule }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.schedule.id.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::from(ErrorKind::InvalidParameters {
+            name: "sync_home_schedule",
+            msg: "schedule has no id; use create_new_home_schedule for new schedules".to_string(),
+        }))
+    }
+}
+
+impl<'a> From<&'a Parameters<'a>> for Value {
+    fn from(p: &'a Parameters) -> Value {
+        json!({
+            "home_id": p.home_id,
+            "schedule_id": p.schedule.id,
+            "name": p.schedule.name,
+            "timetable": p.schedule.timetable,
+            "zones": p.schedule.zones,
+            "hg_temp": p.schedule.hg_temp,
+            "away_temp": p.schedule.away_temp,
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Response {
+    pub status: String,
+    pub time_server: usize,
+}
+
+impl ApiResponse for Response {}
+
+// cf. https://dev.netatmo.com/apidocumentation/energy#synchomeschedule
+#[cfg(feature = "blocking")]
+pub fn sync_home_schedule(client: &AuthenticatedClient, parameters: &Parameters) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client.call_write_json("sync_home_schedule", "api/synchomeschedule", &body)
+}
+
+#[cfg(feature = "async")]
+pub async fn sync_home_schedule_async(
+    client: &AsyncAuthenticatedClient,
+    parameters: &Parameters<'_>,
+) -> Result<Response> {
+    parameters.validate()?;
+    let body: Value = parameters.into();
+
+    client
+        .call_write_json("sync_home_schedule", "api/synchomeschedule", &body)
+        .await
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    const SCHEDULE: &str = r#"{
+  "timetable": [{"zone_id": 0, "m_offset": 0}, {"zone_id": 1, "m_offset": 420}],
+  "zones": [
+    {"name": "Comfort", "id": 0, "type": 0, "rooms_temp": [{"room_id": "2255", "temp": 19.5}],
+     "rooms": [{"id": "2255", "therm_setpoint_temperature": 19.5}]},
+    {"name": "Night", "id": 1, "type": 1, "rooms_temp": [{"room_id": "2255", "temp": 16.5}], "modules": []}
+  ],
+  "name": "Default",
+  "default": false,
+  "away_temp": 12,
+  "hg_temp": 7,
+  "id": "5a9e6c56",
+  "selected": true,
+  "type": "therm"
+}"#;
+
+    mod parameters {
+        use super::*;
+
+        #[test]
+        fn round_trip_fetched_schedule() {
+            let fetched: Value = serde_json::from_str(SCHEDULE).unwrap();
+            let schedule: ThermSchedule = serde_json::from_value(fetched.clone()).unwrap();
+            let parameters = Parameters::new("home", &schedule);
+
+            let body: Value = (&parameters).into();
+
+            assert_that(&parameters.validate()).is_ok();
+            assert_that(&body["schedule_id"]).is_equal_to(&fetched["id"]);
+            assert_that(&body["timetable"]).is_equal_to(&fetched["timetable"]);
+            assert_that(&body["zones"]).is_equal_to(&fetched["zones"]);
+        }
+
+        #[test]
+        fn reject_schedule_without_id() {
+            let schedule = ThermSchedule::default();
+
+            assert_that(&Parameters::new("home", &schedule).validate()).is_err();
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod authenticated_client {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{client, now, token, RESPONSE},
+            Netatmo,
+        };
+
+        #[test]
+        fn send_json_with_bearer_token() {
+            let server = FakeServer::start(vec![(200, RESPONSE)]);
+            let client = client(&server, token(now()));
+            let schedule = ThermSchedule {
+                id: "5a9e6c56".to_string(),
+                name: "Default".to_string(),
+                ..ThermSchedule::default()
+            };
+
+            let res = client.sync_home_schedule(&Parameters::new("home", &schedule));
+
+            assert_that(&res).is_ok();
+            let requests = server.requests();
+            let body: Value = serde_json::from_slice(&requests[0].body).expect("Failed to parse body");
+            assert_that(&requests[0].target).is_equal_to("/api/synchomeschedule".to_string());
+            assert_that(&requests[0].headers["content-type"]).is_equal_to("application/json".to_string());
+            assert_that(&requests[0].headers["authorization"]).is_equal_to("Bearer abcdef|123456".to_string());
+            assert_that(&body["schedule_id"].as_str()).is_equal_to(Some("5a9e6c56"));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Fixtures shared by the tests that call a `FakeServer` through a blocking or an async client
+
+#[cfg(feature = "blocking")]
+use std::collections::HashMap;
+
+#[cfg(feature = "async")]
+use crate::client::AsyncAuthenticatedClient;
+use crate::client::{
+    authenticate::{Scope, Token},
+    local_http::FakeServer,
+    ClientCredentials, NetatmoClient,
+};
+#[cfg(feature = "blocking")]
+use crate::{
+    client::{local_http::Request, set_room_thermpoint, AuthenticatedClient, Netatmo},
+    errors::Result,
+};
+
+pub(crate) const REFRESHED_TOKEN: &str = r#"{
+  "access_token": "abcdef|refreshed",
+  "refresh_token": "abcdef|654321",
+  "scope": ["write_thermostat"],
+  "expires_in": 10800,
+  "expire_in": 10800
+}"#;
+pub(crate) const RESPONSE: &str = r#"{"status": "ok", "time_server": 1580000000}"#;
+#[cfg(feature = "blocking")]
+pub(crate) const STATION_DATA: &str = r#"{
+  "body": {
+    "devices": [],
+    "user": {
+      "mail": "user@example.com",
+      "administrative": {"lang": "en", "reg_locale": "en-US", "unit": 0, "windunit": 0, "feel_like_algo": 0}
+    }
+  },
+  "status": "ok",
+  "time_exec": 0.1,
+  "time_server": 1580000000
+}"#;
+pub(crate) const EXPIRED_TOKEN_ERROR: &str = r#"{"error": {"code": 3, "message": "Access token expired"}}"#;
+
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("System time before Unix epoch")
+        .as_secs()
+}
+
+pub(crate) fn token(issued_at: u64) -> Token {
+    Token {
+        access_token: "abcdef|123456".to_string(),
+        refresh_token: "abcdef|654321".to_string(),
+        scope: vec![Scope::WriteThermostat],
+        expires_in: 10800,
+        expire_in: 10800,
+        issued_at,
+    }
+}
+
+const CLIENT_CREDENTIALS: ClientCredentials<'static> = ClientCredentials {
+    client_id: "my_client_id",
+    client_secret: "my_client_secret",
+};
+
+/// Builds a client with client credentials that sends all calls to `server`
+#[cfg(feature = "blocking")]
+pub(crate) fn client(server: &FakeServer, token: Token) -> AuthenticatedClient {
+    NetatmoClient::builder()
+        .base_url(server.url())
+        .build_with_token(token)
+        .expect("Failed to build client")
+        .with_client_credentials(&CLIENT_CREDENTIALS)
+}
+
+/// Builds an async client with client credentials that sends all calls to `server`
+#[cfg(feature = "async")]
+pub(crate) fn async_client(server: &FakeServer, token: Token) -> AsyncAuthenticatedClient {
+    NetatmoClient::builder()
+        .base_url(server.url())
+        .build_async_with_token(token)
+        .expect("Failed to build client")
+        .with_client_credentials(&CLIENT_CREDENTIALS)
+}
+
+#[cfg(feature = "blocking")]
+pub(crate) fn set_room_thermpoint(client: &AuthenticatedClient) -> Result<set_room_thermpoint::Response> {
+    let parameters = set_room_thermpoint::Parameters::new("home", "room", set_room_thermpoint::Mode::Home);
+    client.set_room_thermpoint(&parameters)
+}
+
+/// Decodes the form encoded body of `request`
+#[cfg(feature = "blocking")]
+pub(crate) fn form(request: &Request) -> HashMap<String, String> {
+    reqwest::Url::parse(&format!("http://localhost/?{}", String::from_utf8_lossy(&request.body)))
+        .expect("Failed to parse form")
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
@@ -0,0 +1,374 @@
+use crate::{
+    client::authenticate::Token,
+    errors::{ErrorKind, Result},
+};
+
+use failure::Fail;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// Numbers the temporary files of concurrent saves within this process
+static TMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Persists tokens so that they survive the process that obtained them
+///
+/// `AuthenticatedClient` writes refreshed tokens back to its store.
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Result<Option<Token>>;
+    fn save(&self, token: &Token) -> Result<()>;
+    fn clear(&self) -> Result<()>;
+}
+
+impl<T: TokenStore + ?Sized> TokenStore for Arc<T> {
+    fn load(&self) -> Result<Option<Token>> {
+        (**self).load()
+    }
+
+    fn save(&self, token: &Token) -> Result<()> {
+        (**self).save(token)
+    }
+
+    fn clear(&self) -> Result<()> {
+        (**self).clear()
+    }
+}
+
+/// Stores a token as JSON file readable and writable by the current user only
+///
+/// Saving writes to a temporary file in the same directory first and then renames it, so readers never see a
+/// partially written token. Every save uses its own temporary file, so clients sharing a store may save concurrently.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        FileTokenStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let file_name = self
+            .path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let n = TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        self.path
+            .with_file_name(format!(".{}.{}.{}.tmp", file_name, process::id(), n))
+    }
+
+    fn write_tmp_file(&self, tmp_path: &Path, json: &[u8]) -> io::Result<()> {
+        if let Some(dir) = self.path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(tmp_path)?;
+        file.write_all(json)?;
+        file.sync_all()
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<Token>> {
+        let json = match fs::read(&self.path) {
+            Ok(json) => json,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.context(ErrorKind::TokenStoreFailed).into()),
+        };
+        let token = serde_json::from_slice(&json).map_err(|e| e.context(ErrorKind::TokenStoreFailed))?;
+
+        Ok(Some(token))
+    }
+
+    fn save(&self, token: &Token) -> Result<()> {
+        let json = serde_json::to_vec_pretty(token).map_err(|e| e.context(ErrorKind::TokenStoreFailed))?;
+        let tmp_path = self.tmp_path();
+        self.write_tmp_file(&tmp_path, &json)
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&tmp_path);
+                e.context(ErrorKind::TokenStoreFailed).into()
+            })
+    }
+
+    fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.context(ErrorKind::TokenStoreFailed).into()),
+        }
+    }
+}
+
+/// Keeps a token in memory, e.g., to share it between several clients of the same process
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<Token>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        MemoryTokenStore::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self) -> Result<Option<Token>> {
+        Ok(self.token.lock().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+
+    fn save(&self, token: &Token) -> Result<()> {
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) = Some(token.clone());
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        *self.token.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use crate::client::authenticate::Scope;
+
+    fn token() -> Token {
+        Token {
+            access_token: "abcdef|123456".to_string(),
+            refresh_token: "abcdef|654321".to_string(),
+            scope: vec![Scope::ReadStation],
+            expires_in: 10800,
+            expire_in: 10800,
+            issued_at: 1_556_451_492,
+        }
+    }
+
+    mod file_token_store {
+        use super::*;
+
+        fn store(name: &str) -> FileTokenStore {
+            let dir = std::env::temp_dir().join(format!("netatmo-rs-test-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            FileTokenStore::new(dir.join("token.json"))
+        }
+
+        fn dir_entries(store: &FileTokenStore) -> Vec<String> {
+            let dir = store.path().parent().expect("Token file has a directory");
+            fs::read_dir(dir)
+                .expect("Failed to read directory")
+                .map(|x| {
+                    x.expect("Failed to read entry")
+                        .file_name()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect()
+        }
+
+        #[test]
+        fn save_and_load() {
+            let store = store("save_and_load");
+
+            store.save(&token()).expect("Failed to save token");
+            let loaded = store.load().expect("Failed to load token");
+
+            assert_that(&loaded.map(|x| x.refresh_token))
+                .is_some()
+                .is_equal_to("abcdef|654321".to_string());
+            assert_that(&dir_entries(&store)).is_equal_to(vec!["token.json".to_string()]);
+        }
+
+        #[test]
+        fn save_concurrently() {
+            let store = store("save_concurrently");
+
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let store = store.clone();
+                    std::thread::spawn(move || {
+                        for j in 0..20 {
+                            let token = Token {
+                                access_token: format!("abcdef|{}-{}", i, j),
+                                ..token()
+                            };
+                            store.save(&token).expect("Failed to save token");
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().expect("Failed to join saving thread");
+            }
+            let loaded = store.load().expect("Failed to load token");
+
+            assert_that(&loaded.map(|x| x.access_token.starts_with("abcdef|")))
+                .is_some()
+                .is_true();
+            assert_that(&dir_entries(&store)).is_equal_to(vec!["token.json".to_string()]);
+        }
+
+        #[test]
+        fn load_missing() {
+            let store = store("load_missing");
+
+            let loaded = store.load().expect("Failed to load token");
+
+            assert_that(&loaded.is_none()).is_true();
+        }
+
+        #[test]
+        fn clear() {
+            let store = store("clear");
+            store.save(&token()).expect("Failed to save token");
+
+            store.clear().expect("Failed to clear token");
+
+            assert_that(&store.path().exists()).is_false();
+            assert_that(&store.clear().is_ok()).is_true();
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn restricts_permissions() {
+            use std::os::unix::fs::PermissionsExt;
+            let store = store("restricts_permissions");
+
+            store.save(&token()).expect("Failed to save token");
+            let mode = fs::metadata(store.path())
+                .expect("Failed to read metadata")
+                .permissions()
+                .mode();
+
+            assert_that(&(mode & 0o777)).is_equal_to(0o600);
+        }
+    }
+
+    mod memory_token_store {
+        use super::*;
+
+        #[test]
+        fn save_load_and_clear() {
+            let store = MemoryTokenStore::new();
+
+            store.save(&token()).expect("Failed to save token");
+            assert_that(&store.load().expect("Failed to load token").is_some()).is_true();
+            store.clear().expect("Failed to clear token");
+            assert_that(&store.load().expect("Failed to load token").is_none()).is_true();
+        }
+    }
+
+    #[cfg(feature = "blocking")]
+    mod authenticated_client {
+        use super::*;
+        use crate::client::{
+            local_http::FakeServer,
+            test_support::{client, form, now, set_room_thermpoint, token, REFRESHED_TOKEN, RESPONSE},
+        };
+        use std::sync::Arc;
+
+        #[test]
+        fn save_refreshed_token_to_token_store() {
+            let server = FakeServer::start(vec![(200, REFRESHED_TOKEN), (200, RESPONSE)]);
+            let token_store = Arc::new(MemoryTokenStore::new());
+            let client = client(&server, token(now() - 10800))
+                .with_token_store(token_store.clone())
+                .expect("Failed to save token");
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            let stored = token_store.load().expect("Failed to load token");
+            assert_that(&stored.map(|x| x.access_token))
+                .is_some()
+                .is_equal_to("abcdef|refreshed".to_string());
+        }
+
+        #[test]
+        fn keep_newer_token_of_token_store() {
+            let server = FakeServer::start(vec![(200, RESPONSE)]);
+            let token_store = Arc::new(MemoryTokenStore::new());
+            let newer = Token {
+                access_token: "abcdef|newer".to_string(),
+                ..token(now())
+            };
+            token_store.save(&newer).expect("Failed to save token");
+            let client = client(&server, token(now() - 10800))
+                .with_token_store(token_store.clone())
+                .expect("Failed to sync token");
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            let stored = token_store.load().expect("Failed to load token");
+            assert_that(&stored.map(|x| x.access_token))
+                .is_some()
+                .is_equal_to("abcdef|newer".to_string());
+            assert_that(&form(&server.requests()[0])["access_token"]).is_equal_to("abcdef|newer".to_string());
+        }
+
+        #[test]
+        fn replace_older_token_of_token_store() {
+            let server = FakeServer::start(vec![]);
+            let token_store = Arc::new(MemoryTokenStore::new());
+            token_store.save(&token(now() - 10800)).expect("Failed to save token");
+
+            let client = client(
+                &server,
+                Token {
+                    access_token: "abcdef|newer".to_string(),
+                    ..token(now())
+                },
+            )
+            .with_token_store(token_store.clone())
+            .expect("Failed to sync token");
+
+            let stored = token_store.load().expect("Failed to load token");
+            assert_that(&stored.map(|x| x.access_token))
+                .is_some()
+                .is_equal_to("abcdef|newer".to_string());
+            assert_that(&client.token().access_token).is_equal_to("abcdef|newer".to_string());
+        }
+
+        #[test]
+        fn prefer_newer_token_from_token_store() {
+            let server = FakeServer::start(vec![(200, RESPONSE)]);
+            let token_store = Arc::new(MemoryTokenStore::new());
+            let client = client(&server, token(now() - 10800))
+                .with_token_store(token_store.clone())
+                .expect("Failed to save token");
+            let newer = Token {
+                access_token: "abcdef|newer".to_string(),
+                ..token(now())
+            };
+            token_store.save(&newer).expect("Failed to save token");
+
+            let res = set_room_thermpoint(&client);
+
+            assert_that(&res).is_ok();
+            assert_that(&form(&server.requests()[0])["access_token"]).is_equal_to("abcdef|newer".to_string());
+        }
+    }
+}
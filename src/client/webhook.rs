@@ -0,0 +1,416 @@
+//! Receives the events Netatmo pushes to a webhook registered via `add_webhook`
+//!
+//! Netatmo needs to reach the server from the internet, so it usually runs behind a reverse proxy terminating TLS.
+//! Enable `WebhookServer::verify_signatures` to only accept calls signed by Netatmo.
+
+use crate::{
+    client::{
+        get_home_data::{EventType, Image},
+        local_http, ClientCredentials,
+    },
+    errors::{Error, ErrorKind, Result},
+};
+
+use failure::Fail;
+use log::{debug, warn};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+/// Event pushed by Netatmo; which fields are set depends on `push_type`
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Payload {
+    pub user_id: Option<String>,
+    /// E.g., `NACamera-person`, `NOC-human` or `webhook_activation`
+    pub push_type: Option<String>,
+    pub event_type: Option<String>,
+    pub event_id: Option<String>,
+    pub home_id: Option<String>,
+    pub home_name: Option<String>,
+    pub camera_id: Option<String>,
+    pub device_id: Option<String>,
+    pub module_id: Option<String>,
+    pub room_id: Option<String>,
+    pub message: Option<String>,
+    pub sub_type: Option<i64>,
+    pub is_arrival: Option<bool>,
+    #[serde(default)]
+    pub persons: Vec<Person>,
+    pub snapshot_id: Option<String>,
+    pub snapshot_key: Option<String>,
+    pub snapshot_url: Option<String>,
+    pub vignette_id: Option<String>,
+    pub vignette_key: Option<String>,
+    pub vignette_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Payload {
+    /// Checks if this is the test call Netatmo sends right after registering the webhook
+    pub fn is_activation(&self) -> bool {
+        self.push_type.as_deref() == Some("webhook_activation")
+    }
+
+    pub fn event_type(&self) -> Option<EventType> {
+        self.event_type.as_deref().map(EventType::from)
+    }
+
+    pub fn snapshot(&self) -> Option<Image> {
+        image(&self.snapshot_id, &self.snapshot_key, &self.snapshot_url)
+    }
+
+    pub fn vignette(&self) -> Option<Image> {
+        image(&self.vignette_id, &self.vignette_key, &self.vignette_url)
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Person {
+    pub id: String,
+    #[serde(default)]
+    pub is_known: bool,
+    pub face_id: Option<String>,
+    pub face_key: Option<String>,
+    pub face_url: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl Person {
+    pub fn face(&self) -> Option<Image> {
+        image(&self.face_id, &self.face_key, &self.face_url)
+    }
+}
+
+fn image(id: &Option<String>, key: &Option<String>, url: &Option<String>) -> Option<Image> {
+    if id.is_none() && url.is_none() {
+        return None;
+    }
+
+    Some(Image {
+        id: id.clone(),
+        key: key.clone(),
+        url: url.clone(),
+        ..Image::default()
+    })
+}
+
+/// Header carrying the hex encoded HMAC-SHA256 of the request body keyed with the client secret
+pub const SIGNATURE_HEADER: &str = "x-netatmo-secret";
+
+/// Checks that a webhook call has been signed by Netatmo for the application with the given client secret
+pub struct SignatureVerifier {
+    key: hmac::Key,
+}
+
+impl SignatureVerifier {
+    pub fn new(client_credentials: &ClientCredentials) -> Self {
+        SignatureVerifier {
+            key: hmac::Key::new(hmac::HMAC_SHA256, client_credentials.client_secret.as_bytes()),
+        }
+    }
+
+    /// Verifies `signature`, the value of `SIGNATURE_HEADER`, against the raw `body`
+    pub fn verify(&self, signature: Option<&str>, body: &[u8]) -> Result<()> {
+        let signature = signature
+            .and_then(decode_hex)
+            .ok_or_else(|| Error::from(ErrorKind::InvalidWebhookSignature))?;
+        hmac::verify(&self.key, body, &signature).map_err(|_| Error::from(ErrorKind::InvalidWebhookSignature))
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// HTTP listener receiving webhook calls on a local port
+pub struct WebhookServer {
+    listener: TcpListener,
+    verifier: Option<SignatureVerifier>,
+    read_timeout: Duration,
+}
+
+impl WebhookServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<WebhookServer> {
+        let listener = TcpListener::bind(addr).map_err(|e| e.context(ErrorKind::WebhookServerFailed))?;
+
+        Ok(WebhookServer {
+            listener,
+            verifier: None,
+            read_timeout: local_http::READ_TIMEOUT,
+        })
+    }
+
+    /// Drops connections that stay silent for longer than `read_timeout` while sending a request; defaults to 10 s
+    pub fn read_timeout(self, read_timeout: Duration) -> Self {
+        WebhookServer { read_timeout, ..self }
+    }
+
+    /// Rejects calls without a valid signature for the application of `client_credentials` with status 401
+    pub fn verify_signatures(self, client_credentials: &ClientCredentials) -> Self {
+        WebhookServer {
+            verifier: Some(SignatureVerifier::new(client_credentials)),
+            ..self
+        }
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .map_err(|e| e.context(ErrorKind::WebhookServerFailed).into())
+    }
+
+    /// Blocks until the next well-formed payload arrives; malformed requests and broken connections are skipped
+    pub fn next_payload(&self) -> Result<Payload> {
+        loop {
+            let (mut stream, peer) = match local_http::accept(&self.listener, self.read_timeout)
+                .map_err(|e| e.context(ErrorKind::WebhookServerFailed))?
+            {
+                Some(accepted) => accepted,
+                None => continue,
+            };
+            let request = match local_http::read_request(&mut stream) {
+                Ok(request) => request,
+                Err(e) => {
+                    warn!("Failed to read webhook request from {}: {}", peer, e);
+                    let _ = local_http::write_response(&mut stream, 400, "text/plain", b"");
+                    continue;
+                }
+            };
+            debug!("Received '{} {}' from {}", request.method, request.target, peer);
+            if request.method != "POST" {
+                let _ = local_http::write_response(&mut stream, 405, "text/plain", b"");
+                continue;
+            }
+            if let Some(ref verifier) = self.verifier {
+                let signature = request.headers.get(SIGNATURE_HEADER).map(String::as_str);
+                if let Err(e) = verifier.verify(signature, &request.body) {
+                    warn!("Rejected webhook call from {}: {}", peer, e);
+                    let _ = local_http::write_response(&mut stream, 401, "text/plain", b"");
+                    continue;
+                }
+            }
+
+            match serde_json::from_slice(&request.body) {
+                Ok(payload) => {
+                    let _ = local_http::write_response(&mut stream, 200, "text/plain", b"");
+                    return Ok(payload);
+                }
+                Err(e) => {
+                    warn!("Failed to parse webhook payload from {}: {}", peer, e);
+                    let _ = local_http::write_response(&mut stream, 400, "text/plain", b"");
+                }
+            }
+        }
+    }
+
+    /// Passes every payload to `handler` until accepting connections fails
+    pub fn serve<F: FnMut(Payload)>(&self, mut handler: F) -> Result<()> {
+        loop {
+            handler(self.next_payload()?);
+        }
+    }
+
+    /// Sends every payload to `sender` until the receiver hangs up or accepting connections fails
+    pub fn serve_channel(&self, sender: Sender<Payload>) -> Result<()> {
+        loop {
+            if sender.send(self.next_payload()?).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+        sync::mpsc,
+        thread,
+    };
+
+    const PERSON_PAYLOAD: &str = r#"{
+  "user_id": "5a1b2c3d4e5f6a7b8c9d0e1f",
+  "event_type": "person",
+  "event_id": "5e2f0002",
+  "camera_id": "70:ee:50:00:00:01",
+  "device_id": "70:ee:50:00:00:01",
+  "home_id": "5a1b2c3d4e5f6a7b8c9d0e20",
+  "home_name": "Home",
+  "message": "Jane seen",
+  "persons": [
+    {"id": "91827364-5555-4444-3333-222211110000", "face_id": "5e2f0000", "face_key": "a1b2c3", "is_known": true, "face_url": "https://netatmocameraimage.blob.core.windows.net/face"}
+  ],
+  "snapshot_id": "5e2f0003",
+  "snapshot_key": "g7h8i9",
+  "snapshot_url": "https://netatmocameraimage.blob.core.windows.net/snapshot",
+  "push_type": "NACamera-person"
+}"#;
+
+    fn post(addr: SocketAddr, method: &str, body: &str) -> String {
+        send(addr, method, "", body)
+    }
+
+    fn send(addr: SocketAddr, method: &str, headers: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(addr).expect("Failed to connect to webhook server");
+        write!(
+            stream,
+            "{} / HTTP/1.1\r\nHost: localhost\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            headers,
+            body.len(),
+            body
+        )
+        .expect("Failed to write");
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+
+        response
+    }
+
+    #[test]
+    fn receive_recorded_payload() {
+        let server = WebhookServer::bind("127.0.0.1:0").expect("Failed to bind webhook server");
+        let addr = server.local_addr().expect("Failed to get local address");
+        let client = thread::spawn(move || {
+            (
+                post(addr, "GET", ""),
+                post(addr, "POST", "{"),
+                post(addr, "POST", PERSON_PAYLOAD),
+            )
+        });
+
+        let payload = server.next_payload().expect("Failed to receive payload");
+
+        let (get, malformed, ok) = client.join().expect("Client panicked");
+        assert_that(&get).starts_with("HTTP/1.1 405");
+        assert_that(&malformed).starts_with("HTTP/1.1 400");
+        assert_that(&ok).starts_with("HTTP/1.1 200");
+        assert_that(&payload.event_type()).is_equal_to(Some(EventType::Person));
+        assert_that(&payload.snapshot().and_then(|x| x.key)).is_equal_to(Some("g7h8i9".to_string()));
+        assert_that(&payload.persons[0].is_known).is_true();
+        assert_that(&payload.extra).is_empty();
+    }
+
+    #[test]
+    fn skip_idle_and_oversized_requests() {
+        let server = WebhookServer::bind("127.0.0.1:0")
+            .expect("Failed to bind webhook server")
+            .read_timeout(Duration::from_millis(100));
+        let addr = server.local_addr().expect("Failed to get local address");
+        let client = thread::spawn(move || {
+            let idle = TcpStream::connect(addr).expect("Failed to connect to webhook server");
+            let oversized = post(addr, &"A".repeat(10 * 1024), "");
+            let ok = post(addr, "POST", PERSON_PAYLOAD);
+            drop(idle);
+            (oversized, ok)
+        });
+
+        let payload = server.next_payload().expect("Failed to receive payload");
+
+        let (oversized, ok) = client.join().expect("Client panicked");
+        assert_that(&oversized).starts_with("HTTP/1.1 400");
+        assert_that(&ok).starts_with("HTTP/1.1 200");
+        assert_that(&payload.event_type()).is_equal_to(Some(EventType::Person));
+    }
+
+    #[test]
+    fn serve_channel_until_receiver_hangs_up() {
+        let server = WebhookServer::bind("127.0.0.1:0").expect("Failed to bind webhook server");
+        let addr = server.local_addr().expect("Failed to get local address");
+        let (sender, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || server.serve_channel(sender));
+
+        post(
+            addr,
+            "POST",
+            r#"{"user_id": "5a1b2c3d4e5f6a7b8c9d0e1f", "push_type": "webhook_activation"}"#,
+        );
+        let payload = receiver.recv().expect("Failed to receive payload");
+        drop(receiver);
+        post(addr, "POST", PERSON_PAYLOAD);
+
+        assert_that(&payload.is_activation()).is_true();
+        assert_that(&handle.join().expect("Server panicked")).is_ok();
+    }
+
+    mod signature_verifier {
+        use super::*;
+
+        const CLIENT_CREDENTIALS: ClientCredentials = ClientCredentials {
+            client_id: "my_client_id",
+            client_secret: "key",
+        };
+        const BODY: &[u8] = b"The quick brown fox jumps over the lazy dog";
+        const SIGNATURE: &str = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+
+        #[test]
+        fn accept_valid_signature() {
+            let verifier = SignatureVerifier::new(&CLIENT_CREDENTIALS);
+
+            assert_that(&verifier.verify(Some(SIGNATURE), BODY)).is_ok();
+            assert_that(&verifier.verify(Some(&SIGNATURE.to_uppercase()), BODY)).is_ok();
+        }
+
+        #[test]
+        fn reject_tampered_or_unsigned_body() {
+            let verifier = SignatureVerifier::new(&CLIENT_CREDENTIALS);
+
+            for (signature, body) in &[
+                (Some(SIGNATURE), &b"The quick brown fox jumps over the lazy cat"[..]),
+                (Some("f7bc"), BODY),
+                (Some("not hex"), BODY),
+                (None, BODY),
+            ] {
+                let res = verifier.verify(*signature, body).map_err(|e| e.kind().clone());
+
+                assert_that(&res).is_err_containing(ErrorKind::InvalidWebhookSignature);
+            }
+        }
+
+        #[test]
+        fn server_rejects_unsigned_calls() {
+            let server = WebhookServer::bind("127.0.0.1:0")
+                .expect("Failed to bind webhook server")
+                .verify_signatures(&CLIENT_CREDENTIALS);
+            let addr = server.local_addr().expect("Failed to get local address");
+            let body = r#"{"push_type": "webhook_activation"}"#;
+            let signature = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, b"key"), body.as_bytes())
+                .as_ref()
+                .iter()
+                .map(|x| format!("{:02x}", x))
+                .collect::<String>();
+            let client = thread::spawn(move || {
+                (
+                    post(addr, "POST", PERSON_PAYLOAD),
+                    send(addr, "POST", &format!("X-Netatmo-Secret: {}\r\n", signature), body),
+                )
+            });
+
+            let payload = server.next_payload().expect("Failed to receive payload");
+
+            let (unsigned, signed) = client.join().expect("Client panicked");
+            assert_that(&unsigned).starts_with("HTTP/1.1 401");
+            assert_that(&signed).starts_with("HTTP/1.1 200");
+            assert_that(&payload.is_activation()).is_true();
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    client::get_homes_data::{ThermSchedule, Timetable, Zone},
+    errors::{Error, ErrorKind, Result},
+};
+
+use std::fmt;
+
+/// `Timetable::m_offset` counts minutes since Monday 00:00
+const MINUTES_PER_DAY: i64 = 24 * 60;
+const MINUTES_PER_WEEK: i64 = 7 * MINUTES_PER_DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+impl Weekday {
+    /// Days from `self` through `last`, e.g., `Weekday::Mon.to(Weekday::Fri)`; `Weekday::Sat.to(Weekday::Mon)` wraps
+    pub fn to(self, last: Weekday) -> Days {
+        Days { first: self, last }
+    }
+
+    fn parse(s: &str) -> Option<Weekday> {
+        WEEKDAYS.iter().copied().find(|x| x.to_string().eq_ignore_ascii_case(s))
+    }
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Weekday::Mon => "Mon",
+            Weekday::Tue => "Tue",
+            Weekday::Wed => "Wed",
+            Weekday::Thu => "Thu",
+            Weekday::Fri => "Fri",
+            Weekday::Sat => "Sat",
+            Weekday::Sun => "Sun",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Consecutive weekdays, written like `Mon-Fri`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Days {
+    first: Weekday,
+    last: Weekday,
+}
+
+impl Days {
+    fn iter(self) -> impl Iterator<Item = Weekday> {
+        let len = (self.last as usize + 7 - self.first as usize) % 7 + 1;
+        (0..len).map(move |i| WEEKDAYS[(self.first as usize + i) % 7])
+    }
+
+    fn parse(s: &str) -> Option<Days> {
+        match s.split_once('-') {
+            Some((first, last)) => Some(Weekday::parse(first)?.to(Weekday::parse(last)?)),
+            None => Weekday::parse(s).map(Days::from),
+        }
+    }
+}
+
+impl From<Weekday> for Days {
+    fn from(day: Weekday) -> Self {
+        day.to(day)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Switch {
+    day: Weekday,
+    hour: u8,
+    minute: u8,
+    zone: String,
+}
+
+impl Switch {
+    fn m_offset(&self) -> i64 {
+        self.day as i64 * MINUTES_PER_DAY + i64::from(self.hour) * 60 + i64::from(self.minute)
+    }
+}
+
+/// Weekly program of a `ThermSchedule` in terms of weekdays, times of day and zone names
+///
+/// Its readable form lists the times at which each group of days switches zones, e.g.,
+/// `Mon-Fri 06:30 Comfort, 08:00 Eco, Sat-Sun 08:00 Comfort`; days without switches keep the previous zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeeklySchedule {
+    zones: Vec<Zone>,
+    switches: Vec<Switch>,
+}
+
+impl WeeklySchedule {
+    /// Starts an empty program switching between `zones`
+    pub fn new(zones: Vec<Zone>) -> Self {
+        WeeklySchedule {
+            zones,
+            switches: Vec::new(),
+        }
+    }
+
+    /// Switches to the zone named `zone` at `hour:minute` on each of `days`
+    pub fn at<D: Into<Days>>(mut self, days: D, hour: u8, minute: u8, zone: &str) -> Self {
+        for day in days.into().iter() {
+            self.switches.push(Switch {
+                day,
+                hour,
+                minute,
+                zone: zone.to_string(),
+            });
+        }
+        self.switches.sort_by_key(|x| (x.day, x.hour, x.minute));
+        self
+    }
+
+    /// Parses the readable form; zone names are checked by `timetable`
+    pub fn parse(zones: Vec<Zone>, s: &str) -> Result<Self> {
+        let mut schedule = WeeklySchedule::new(zones);
+        let mut days = None;
+        for entry in s.split(',').map(str::trim) {
+            let mut rest = entry;
+            if let Some(parsed) = entry.split_whitespace().next().and_then(Days::parse) {
+                days = Some(parsed);
+                rest = entry.split_once(char::is_whitespace).map(|x| x.1).unwrap_or("");
+            }
+            let days = days.ok_or_else(|| invalid_schedule(format!("'{}' does not start with weekdays", entry)))?;
+            let (time, zone) = rest
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| invalid_schedule(format!("'{}' lacks time or zone", entry)))?;
+            let (hour, minute) = parse_time(time)?;
+            schedule = schedule.at(days, hour, minute, zone.trim());
+        }
+
+        Ok(schedule)
+    }
+
+    /// Decompiles timetable and zones of `schedule`
+    ///
+    /// The switch at Monday 00:00 continuing Sunday's last zone is implied and therefore left out.
+    pub fn from_therm_schedule(schedule: &ThermSchedule) -> Result<Self> {
+        let mut timetable: Vec<&Timetable> = schedule.timetable.iter().collect();
+        timetable.sort_by_key(|x| x.m_offset);
+        if let (Some(first), Some(last)) = (timetable.first(), timetable.last()) {
+            if timetable.len() > 1 && first.m_offset == 0 && first.zone_id == last.zone_id {
+                timetable.remove(0);
+            }
+        }
+
+        let mut switches = Vec::with_capacity(timetable.len());
+        for entry in timetable {
+            if !(0..MINUTES_PER_WEEK).contains(&entry.m_offset) {
+                return Err(invalid_schedule(format!(
+                    "offset {} is outside the week",
+                    entry.m_offset
+                )));
+            }
+            let zone = schedule
+                .zones
+                .iter()
+                .find(|x| x.id == entry.zone_id)
+                .ok_or_else(|| invalid_schedule(format!("zone {} does not exist", entry.zone_id)))?;
+            let minute_of_day = entry.m_offset % MINUTES_PER_DAY;
+            switches.push(Switch {
+                day: WEEKDAYS[(entry.m_offset / MINUTES_PER_DAY) as usize],
+                hour: (minute_of_day / 60) as u8,
+                minute: (minute_of_day % 60) as u8,
+                zone: zone.name.clone(),
+            });
+        }
+
+        Ok(WeeklySchedule {
+            zones: schedule.zones.clone(),
+            switches,
+        })
+    }
+
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    /// Compiles the program after checking times, zone references and overlapping switches
+    ///
+    /// If no switch happens at Monday 00:00, the timetable starts with Sunday's last zone since Netatmo expects an
+    /// entry at offset 0.
+    pub fn timetable(&self) -> Result<Vec<Timetable>> {
+        let mut timetable: Vec<Timetable> = Vec::with_capacity(self.switches.len() + 1);
+        for switch in &self.switches {
+            if switch.hour > 23 || switch.minute > 59 {
+                return Err(invalid_schedule(format!(
+                    "{} {}:{} is not a valid time",
+                    switch.day, switch.hour, switch.minute
+                )));
+            }
+            let mut zones = self.zones.iter().filter(|x| x.name == switch.zone);
+            let zone_id = match (zones.next(), zones.next()) {
+                (Some(zone), None) => zone.id,
+                (Some(_), Some(_)) => return Err(invalid_schedule(format!("zone '{}' is ambiguous", switch.zone))),
+                (None, _) => return Err(invalid_schedule(format!("zone '{}' does not exist", switch.zone))),
+            };
+            if timetable.last().map(|x| x.m_offset) == Some(switch.m_offset()) {
+                return Err(invalid_schedule(format!(
+                    "switches overlap at {} {:02}:{:02}",
+                    switch.day, switch.hour, switch.minute
+                )));
+            }
+            timetable.push(Timetable {
+                zone_id,
+                m_offset: switch.m_offset(),
+                ..Timetable::default()
+            });
+        }
+
+        let last_zone_id = match timetable.last() {
+            Some(last) => last.zone_id,
+            None => return Err(invalid_schedule("no switches".to_string())),
+        };
+        if timetable[0].m_offset != 0 {
+            timetable.insert(
+                0,
+                Timetable {
+                    zone_id: last_zone_id,
+                    m_offset: 0,
+                    ..Timetable::default()
+                },
+            );
+        }
+
+        Ok(timetable)
+    }
+
+    /// Replaces timetable and zones of `schedule`, e.g., before `sync_home_schedule`
+    pub fn apply_to(&self, schedule: &mut ThermSchedule) -> Result<()> {
+        schedule.timetable = self.timetable()?;
+        schedule.zones = self.zones.clone();
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for WeeklySchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let day_switches = |day: Weekday| -> Vec<&Switch> { self.switches.iter().filter(|x| x.day == day).collect() };
+        let same_switches = |a: &[&Switch], b: &[&Switch]| {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(x, y)| (x.hour, x.minute, &x.zone) == (y.hour, y.minute, &y.zone))
+        };
+
+        let mut entries = Vec::new();
+        let mut i = 0;
+        while i < WEEKDAYS.len() {
+            let switches = day_switches(WEEKDAYS[i]);
+            let mut last = i;
+            while last + 1 < WEEKDAYS.len() && same_switches(&switches, &day_switches(WEEKDAYS[last + 1])) {
+                last += 1;
+            }
+            for (j, switch) in switches.iter().enumerate() {
+                let time = format!("{:02}:{:02} {}", switch.hour, switch.minute, switch.zone);
+                entries.push(match (j, last > i) {
+                    (0, true) => format!("{}-{} {}", WEEKDAYS[i], WEEKDAYS[last], time),
+                    (0, false) => format!("{} {}", WEEKDAYS[i], time),
+                    _ => time,
+                });
+            }
+            i = last + 1;
+        }
+
+        write!(f, "{}", entries.join(", "))
+    }
+}
+
+/// Parses `HH:MM`
+fn parse_time(s: &str) -> Result<(u8, u8)> {
+    s.split_once(':')
+        .filter(|(_, minute)| minute.len() == 2)
+        .and_then(|(hour, minute)| Some((hour.parse().ok()?, minute.parse().ok()?)))
+        .ok_or_else(|| invalid_schedule(format!("'{}' is not a time like 06:30", s)))
+}
+
+fn invalid_schedule(msg: String) -> Error {
+    Error::from(ErrorKind::InvalidSchedule { msg })
+}
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    const READABLE: &str = "Mon-Fri 06:30 Comfort, 22:00 Night, Sat-Sun 08:00 Comfort, 23:00 Night";
+
+    fn zones() -> Vec<Zone> {
+        let zone = |id: i64, name: &str| Zone {
+            id,
+            name: name.to_string(),
+            ..Zone::default()
+        };
+        vec![zone(0, "Comfort"), zone(1, "Night"), zone(4, "Eco")]
+    }
+
+    fn offsets(timetable: &[Timetable]) -> Vec<(i64, i64)> {
+        timetable.iter().map(|x| (x.m_offset, x.zone_id)).collect()
+    }
+
+    mod timetable {
+        use super::*;
+        use Weekday::*;
+
+        #[test]
+        fn compile_from_builder() {
+            let schedule = WeeklySchedule::new(zones())
+                .at(Mon, 6, 30, "Comfort")
+                .at(Mon, 8, 0, "Eco")
+                .at(Sun.to(Mon), 22, 0, "Night");
+
+            let timetable = schedule.timetable();
+
+            assert_that(&timetable.map(|x| offsets(&x))).is_ok().is_equal_to(vec![
+                (0, 1),
+                (390, 0),
+                (480, 4),
+                (1320, 1),
+                (6 * 1440 + 1320, 1),
+            ]);
+        }
+
+        #[test]
+        fn reject_unknown_zones_and_overlaps() {
+            let unknown = WeeklySchedule::new(zones()).at(Mon, 6, 30, "Cozy");
+            let overlap = WeeklySchedule::new(zones())
+                .at(Mon.to(Fri), 6, 30, "Comfort")
+                .at(Wed, 6, 30, "Eco");
+
+            assert_that(&unknown.timetable()).is_err();
+            assert_that(&overlap.timetable()).is_err();
+        }
+    }
+
+    mod readable_form {
+        use super::*;
+
+        #[test]
+        fn round_trip_through_therm_schedule() {
+            let parsed = WeeklySchedule::parse(zones(), READABLE).expect("Failed to parse schedule");
+            let mut schedule = ThermSchedule::default();
+            parsed.apply_to(&mut schedule).expect("Failed to compile schedule");
+
+            let decompiled = WeeklySchedule::from_therm_schedule(&schedule).expect("Failed to decompile schedule");
+
+            assert_that(&decompiled).is_equal_to(&parsed);
+            assert_that(&decompiled.to_string()).is_equal_to(READABLE.to_string());
+        }
+
+        #[test]
+        fn reject_malformed_entries() {
+            assert_that(&WeeklySchedule::parse(zones(), "06:30 Comfort")).is_err();
+            assert_that(&WeeklySchedule::parse(zones(), "Mon 6.30 Comfort")).is_err();
+            assert_that(&WeeklySchedule::parse(zones(), "Mon-Fri 06:30")).is_err();
+        }
+    }
+}
@@ -1,23 +1,65 @@
-use std::fmt;
+// The `Fail` derive expands to impls nested in anonymous constants
+#![allow(unknown_lints, non_local_definitions)]
+
+use std::{fmt, time::Duration};
 
 use failure::{Backtrace, Context, Fail};
+use serde::Deserialize;
 
 /// The error kind for errors that get returned in the crate
 #[derive(Eq, PartialEq, Debug, Fail)]
 pub enum ErrorKind {
-    #[fail(display = "failed to deserialize JSON")]
-    JsonDeserializationFailed,
+    #[fail(display = "failed to deserialize JSON of '{}' at '{}' because {}", name, path, msg)]
+    JsonDeserializationFailed {
+        name: &'static str,
+        path: String,
+        msg: String,
+        /// Raw response body, truncated to `ClientBuilder::error_body_limit`
+        body: String,
+    },
+    #[fail(display = "API call '{}' returned unmapped fields {:?}", name, fields)]
+    UnmappedFields { name: &'static str, fields: Vec<String> },
+    #[fail(display = "failed to build HTTP client")]
+    FailedToBuildClient,
+    #[fail(display = "invalid base URL '{}'", url)]
+    InvalidBaseUrl { url: String },
     #[fail(display = "failed to send request")]
     FailedToSendRequest,
     #[fail(display = "failed to read response")]
     FailedToReadResponse,
     #[fail(display = "failed to authenticate")]
     AuthenticationFailed,
+    #[fail(display = "failed to refresh access token")]
+    TokenRefreshFailed,
+    #[fail(display = "failed to access token store")]
+    TokenStoreFailed,
+    #[fail(display = "failed to access picture cache")]
+    PictureCacheFailed,
+    #[fail(display = "failed to write file '{}'", path)]
+    FailedToWriteFile { path: String },
+    #[fail(display = "failed to receive authorization redirect")]
+    RedirectListenerFailed,
+    #[fail(display = "failed to receive webhook call")]
+    WebhookServerFailed,
+    #[fail(display = "webhook call is unsigned or its signature does not match")]
+    InvalidWebhookSignature,
+    #[fail(display = "authorization has been denied because {}", reason)]
+    AuthorizationDenied { reason: String },
+    #[fail(display = "authorization redirect carries an unexpected state")]
+    AuthorizationStateMismatch,
+    #[fail(display = "invalid parameters for API call '{}' because {}", name, msg)]
+    InvalidParameters { name: &'static str, msg: String },
+    #[fail(display = "invalid schedule because {}", msg)]
+    InvalidSchedule { msg: String },
+    #[fail(display = "rate limit exhausted; retry after {:?}", retry_after)]
+    RateLimited { retry_after: Duration },
     #[fail(display = "API call '{}' failed with code {} because {}", name, code, msg)]
     ApiCallFailed {
         name: &'static str,
-        code: isize,
+        code: ApiErrorCode,
         msg: String,
+        /// HTTP status code of the response carrying the error
+        status_code: u16,
     },
     #[fail(
         display = "API call '{}' failed for unknown reason with status code {}",
@@ -26,24 +68,220 @@ pub enum ErrorKind {
     UnknownApiCallFailure { name: &'static str, status_code: u16 },
 }
 
+impl ErrorKind {
+    /// Checks if the access token or the client credentials are missing, invalid, expired or lack scopes
+    pub fn is_auth_error(&self) -> bool {
+        match self {
+            ErrorKind::AuthenticationFailed | ErrorKind::TokenRefreshFailed => true,
+            ErrorKind::ApiCallFailed { code, .. } => code.is_auth_error(),
+            _ => false,
+        }
+    }
+
+    /// Checks if the same call may succeed later, e.g., after a network failure or an internal server error
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ErrorKind::FailedToSendRequest | ErrorKind::FailedToReadResponse => true,
+            ErrorKind::UnknownApiCallFailure { status_code, .. } => *status_code >= 500,
+            ErrorKind::ApiCallFailed { code, status_code, .. } => *status_code >= 500 || code.is_internal_error(),
+            _ => false,
+        }
+    }
+
+    /// Checks if the client side rate limiter or Netatmo's quota rejected the call
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::RateLimited { .. }
+                | ErrorKind::ApiCallFailed {
+                    code: ApiErrorCode::MaximumUsageReached,
+                    ..
+                }
+        )
+    }
+}
+
 impl Clone for ErrorKind {
     fn clone(&self) -> Self {
         use self::ErrorKind::*;
         match *self {
-            JsonDeserializationFailed => JsonDeserializationFailed,
+            JsonDeserializationFailed {
+                name,
+                ref path,
+                ref msg,
+                ref body,
+            } => JsonDeserializationFailed {
+                name,
+                path: path.clone(),
+                msg: msg.clone(),
+                body: body.clone(),
+            },
+            UnmappedFields { name, ref fields } => UnmappedFields {
+                name,
+                fields: fields.clone(),
+            },
+            FailedToBuildClient => FailedToBuildClient,
+            InvalidBaseUrl { ref url } => InvalidBaseUrl { url: url.clone() },
             FailedToSendRequest => FailedToSendRequest,
             FailedToReadResponse => FailedToReadResponse,
             AuthenticationFailed => AuthenticationFailed,
-            ApiCallFailed { name, code, ref msg } => ApiCallFailed {
+            TokenRefreshFailed => TokenRefreshFailed,
+            TokenStoreFailed => TokenStoreFailed,
+            PictureCacheFailed => PictureCacheFailed,
+            FailedToWriteFile { ref path } => FailedToWriteFile { path: path.clone() },
+            RedirectListenerFailed => RedirectListenerFailed,
+            WebhookServerFailed => WebhookServerFailed,
+            InvalidWebhookSignature => InvalidWebhookSignature,
+            AuthorizationDenied { ref reason } => AuthorizationDenied { reason: reason.clone() },
+            AuthorizationStateMismatch => AuthorizationStateMismatch,
+            InvalidParameters { name, ref msg } => InvalidParameters { name, msg: msg.clone() },
+            InvalidSchedule { ref msg } => InvalidSchedule { msg: msg.clone() },
+            RateLimited { retry_after } => RateLimited { retry_after },
+            ApiCallFailed {
+                name,
+                code,
+                ref msg,
+                status_code,
+            } => ApiCallFailed {
                 name,
                 code,
                 msg: msg.clone(),
+                status_code,
             },
             UnknownApiCallFailure { name, status_code } => UnknownApiCallFailure { name, status_code },
         }
     }
 }
 
+/// Error codes documented at https://dev.netatmo.com/apidocumentation/general#status-ok
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "isize")]
+pub enum ApiErrorCode {
+    AccessTokenMissing,
+    InvalidAccessToken,
+    AccessTokenExpired,
+    /// Internal error reported with code 4
+    InternalError,
+    ApplicationDeactivated,
+    NothingToModify,
+    DeviceNotFound,
+    MissingArguments,
+    /// Internal error reported with code 11
+    ServerError,
+    DeviceOrHomeNotFound,
+    InsufficientScope,
+    IpNotFound,
+    TooManyUsersWithIp,
+    InvalidArgument,
+    ApplicationNotFound,
+    UserNotFound,
+    InvalidDate,
+    MaximumUsageReached,
+    InvalidRefreshToken,
+    MethodUnavailable,
+    UnableToExecute,
+    ProhibitedString,
+    CameraNoSpaceAvailable,
+    InvalidJsonEncoding,
+    DeviceUnreachable,
+    Unknown(isize),
+}
+
+impl ApiErrorCode {
+    /// Returns the numeric code as sent by Netatmo
+    pub fn code(self) -> isize {
+        use self::ApiErrorCode::*;
+        match self {
+            AccessTokenMissing => 1,
+            InvalidAccessToken => 2,
+            AccessTokenExpired => 3,
+            InternalError => 4,
+            ApplicationDeactivated => 5,
+            NothingToModify => 7,
+            DeviceNotFound => 9,
+            MissingArguments => 10,
+            ServerError => 11,
+            DeviceOrHomeNotFound => 12,
+            InsufficientScope => 13,
+            IpNotFound => 19,
+            TooManyUsersWithIp => 20,
+            InvalidArgument => 21,
+            ApplicationNotFound => 22,
+            UserNotFound => 23,
+            InvalidDate => 25,
+            MaximumUsageReached => 26,
+            InvalidRefreshToken => 30,
+            MethodUnavailable => 31,
+            UnableToExecute => 32,
+            ProhibitedString => 33,
+            CameraNoSpaceAvailable => 35,
+            InvalidJsonEncoding => 40,
+            DeviceUnreachable => 41,
+            Unknown(code) => code,
+        }
+    }
+
+    pub fn is_auth_error(self) -> bool {
+        use self::ApiErrorCode::*;
+        matches!(
+            self,
+            AccessTokenMissing
+                | InvalidAccessToken
+                | AccessTokenExpired
+                | ApplicationDeactivated
+                | InsufficientScope
+                | InvalidRefreshToken
+        )
+    }
+
+    pub fn is_internal_error(self) -> bool {
+        matches!(self, ApiErrorCode::InternalError | ApiErrorCode::ServerError)
+    }
+}
+
+impl From<isize> for ApiErrorCode {
+    fn from(code: isize) -> Self {
+        use self::ApiErrorCode::*;
+        match code {
+            1 => AccessTokenMissing,
+            2 => InvalidAccessToken,
+            3 => AccessTokenExpired,
+            4 => InternalError,
+            5 => ApplicationDeactivated,
+            7 => NothingToModify,
+            9 => DeviceNotFound,
+            10 => MissingArguments,
+            11 => ServerError,
+            12 => DeviceOrHomeNotFound,
+            13 => InsufficientScope,
+            19 => IpNotFound,
+            20 => TooManyUsersWithIp,
+            21 => InvalidArgument,
+            22 => ApplicationNotFound,
+            23 => UserNotFound,
+            25 => InvalidDate,
+            26 => MaximumUsageReached,
+            30 => InvalidRefreshToken,
+            31 => MethodUnavailable,
+            32 => UnableToExecute,
+            33 => ProhibitedString,
+            35 => CameraNoSpaceAvailable,
+            40 => InvalidJsonEncoding,
+            41 => DeviceUnreachable,
+            code => Unknown(code),
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiErrorCode::Unknown(code) => write!(f, "{}", code),
+            known => write!(f, "{} ({:?})", known.code(), known),
+        }
+    }
+}
+
 /// The error type for errors that get returned in the lookup module
 #[derive(Debug)]
 pub struct Error {
@@ -55,6 +293,21 @@ impl Error {
     pub fn kind(&self) -> &ErrorKind {
         self.inner.get_context()
     }
+
+    /// cf. `ErrorKind::is_auth_error`
+    pub fn is_auth_error(&self) -> bool {
+        self.kind().is_auth_error()
+    }
+
+    /// cf. `ErrorKind::is_retryable`
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// cf. `ErrorKind::is_rate_limited`
+    pub fn is_rate_limited(&self) -> bool {
+        self.kind().is_rate_limited()
+    }
 }
 
 impl Clone for Error {
@@ -96,3 +349,52 @@ impl From<Context<ErrorKind>> for Error {
 }
 
 pub type Result<T> = ::std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod test {
+    use spectral::prelude::*;
+
+    use super::*;
+
+    mod api_error_code {
+        use super::*;
+
+        #[test]
+        fn round_trip_codes() {
+            for code in 0..50 {
+                assert_that(&ApiErrorCode::from(code).code()).is_equal_to(code);
+            }
+            assert_that(&ApiErrorCode::from(26)).is_equal_to(ApiErrorCode::MaximumUsageReached);
+            assert_that(&ApiErrorCode::from(99)).is_equal_to(ApiErrorCode::Unknown(99));
+        }
+    }
+
+    mod error {
+        use super::*;
+
+        fn api_call_failed(code: isize) -> Error {
+            api_call_failed_with_status(code, 400)
+        }
+
+        fn api_call_failed_with_status(code: isize, status_code: u16) -> Error {
+            Error::from(ErrorKind::ApiCallFailed {
+                name: "get_measure",
+                code: code.into(),
+                msg: "failed".to_string(),
+                status_code,
+            })
+        }
+
+        #[test]
+        fn classify() {
+            assert_that(&api_call_failed(3).is_auth_error()).is_true();
+            assert_that(&api_call_failed(13).is_auth_error()).is_true();
+            assert_that(&api_call_failed(4).is_retryable()).is_true();
+            assert_that(&api_call_failed(26).is_rate_limited()).is_true();
+            assert_that(&api_call_failed(21).is_auth_error()).is_false();
+            assert_that(&api_call_failed(21).is_retryable()).is_false();
+            assert_that(&api_call_failed_with_status(21, 500).is_retryable()).is_true();
+            assert_that(&api_call_failed_with_status(21, 503).is_retryable()).is_true();
+        }
+    }
+}
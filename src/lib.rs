@@ -1,8 +1,34 @@
+#[cfg(not(any(feature = "blocking", feature = "async")))]
+compile_error!("Enable at least one of the features `blocking` and `async`");
+
 pub mod client;
 pub mod errors;
 
 pub use client::{
+    add_webhook,
     authenticate::{self, Scope},
-    get_home_status, get_homes_data, get_measure, get_station_data, set_room_thermpoint, ClientCredentials, Netatmo,
-    NetatmoClient,
+    authorization_code::{self, AuthorizationCodeFlow, RedirectListener},
+    builder::ClientBuilder,
+    create_new_home_schedule, delete_home_schedule, drop_webhook, get_camera_picture, get_events, get_events_until,
+    get_home_data, get_home_status, get_homes_data, get_last_event_of, get_measure, get_next_events, get_public_data,
+    get_room_measure, get_station_data,
+    picture_cache::{self, PictureCache},
+    rate_limit::{self, RateLimitMode, RateLimiter},
+    rename_home_schedule,
+    response::{self, Warning},
+    retry::{self, RetryPolicy},
+    set_persons_away, set_persons_home, set_room_thermpoint, set_state, set_therm_mode, switch_home_schedule,
+    sync_home_schedule,
+    token_store::{self, FileTokenStore, MemoryTokenStore, TokenStore},
+    weekly_schedule::{self, Weekday, WeeklySchedule},
+    ClientCredentials, NetatmoClient,
 };
+
+#[cfg(feature = "schedule_evaluator")]
+pub use client::schedule_evaluator::{self, ScheduleEvaluator};
+#[cfg(feature = "webhook")]
+pub use client::webhook::{self, WebhookServer};
+#[cfg(feature = "async")]
+pub use client::{AsyncAuthenticatedClient, AsyncNetatmo, AsyncUnauthenticatedClient};
+#[cfg(feature = "blocking")]
+pub use client::{AuthenticatedClient, Netatmo, UnauthenticatedClient};